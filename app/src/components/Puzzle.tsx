@@ -28,6 +28,7 @@ import { db, GameStateKey } from "../db/db";
 import { Result } from "../db/Result";
 import StatsContext from "../providers/StatsContext";
 import { Difficulty, fetchPuzzle, Loc, Mode, Seed } from "../query/api";
+import localToday from "../util/localToday";
 import Coin from "./Coin";
 import CoinExample from "./CoinExample";
 import CoinPlaceholder from "./CoinPlaceholder";
@@ -53,7 +54,7 @@ export default function Puzzle({
 }: PuzzleProps) {
   const [openStatsDialog] = useContext(StatsContext);
 
-  const [today, setToday] = useState(DateTime.utc().startOf("day"));
+  const [today, setToday] = useState(localToday());
   const date = useMemo(() => (seed === Seed.Today ? +today : 0), [seed, today]);
 
   const query = useQuery({
@@ -138,17 +139,20 @@ export default function Puzzle({
     game.mode === state.mode;
 
   const [diff, setDiff] = useState<Duration>(
-    today.plus({ days: 1 }).diffNow(["hours", "minutes", "seconds"]),
+    DateTime.local()
+      .plus({ days: 1 })
+      .startOf("day")
+      .diffNow(["hours", "minutes", "seconds"]),
   );
 
   useEffect(() => {
     if (isFullyLoaded && seed === Seed.Today) {
-      const nextDay = today.plus({ days: 1 });
+      const nextDay = DateTime.local().plus({ days: 1 }).startOf("day");
       const interval = setInterval(() => {
         const diff = nextDay.diffNow(["hours", "minutes", "seconds"]);
-        if (+today !== +DateTime.utc().startOf("day")) {
+        if (+today !== +localToday()) {
           console.log("Going to next day");
-          setToday(DateTime.utc().startOf("day"));
+          setToday(localToday());
         }
         setDiff(diff);
       }, 1_000);
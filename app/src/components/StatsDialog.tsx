@@ -16,6 +16,7 @@ import { DateTime } from "ts-luxon";
 import { db, GameState } from "../db/db";
 import { Result } from "../db/Result";
 import { Mode } from "../query/api";
+import localToday from "../util/localToday";
 import toFixed from "../util/toFixed";
 import CustomToast from "./CustomToast";
 
@@ -101,7 +102,7 @@ export default function StatsDialog({
     let addedGuessCount = null;
     let addedHintCount = null;
     let copyText = "";
-    if (games.length && games[0].date === +DateTime.utc().startOf("day")) {
+    if (games.length && games[0].date === +localToday()) {
       changedStats = true;
       copyText = score(games[0]);
       if (games[0].result === Result.Win) {
@@ -4,6 +4,7 @@ export interface ResPuzzle {
   answer: string;
   answer_meta: ResKanjiMeta;
   difficulty: Difficulty;
+  date: string | null;
 }
 
 export interface ResHint {
@@ -62,7 +63,10 @@ export async function fetchPuzzle(
 ): Promise<ResPuzzle> {
   const r = await fetch(
     `${import.meta.env.VITE_API_URL}/v1/${seed}?mode=${mode}` +
-      (difficulty ? `&difficulty=${difficulty}` : ""),
+      (difficulty ? `&difficulty=${difficulty}` : "") +
+      (seed === Seed.Today
+        ? `&tz=${Intl.DateTimeFormat().resolvedOptions().timeZone}`
+        : ""),
     {
       method: "GET",
     },
@@ -0,0 +1,8 @@
+import { DateTime } from "ts-luxon";
+
+// Daily puzzles roll over at local midnight, and are keyed by midnight UTC of the local date
+export default function localToday(): DateTime {
+  return DateTime.local()
+    .startOf("day")
+    .setZone("utc", { keepLocalTime: true });
+}
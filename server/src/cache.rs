@@ -0,0 +1,128 @@
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::NaiveDate;
+use indexmap::IndexMap;
+use tokio::sync::OnceCell;
+
+// Everything a served puzzle depends on
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PuzzleKey {
+    pub date: NaiveDate,
+    pub mode: &'static str,
+    pub difficulty: &'static str,
+    pub data_version: String,
+    // Of the remaining options, such as the schedule of a guild or the theme
+    pub options_hash: u64,
+}
+
+#[derive(Debug)]
+struct Entry<V> {
+    value: Arc<OnceCell<V>>,
+    created: Instant,
+}
+
+// Least recently used entries are evicted first, and none is kept longer than the ttl
+#[derive(Debug)]
+pub struct PuzzleCache<K, V> {
+    entries: Mutex<IndexMap<K, Entry<V>>>,
+    capacity: usize,
+    ttl: Duration,
+}
+
+impl<K: Hash + Eq, V: Clone> PuzzleCache<K, V> {
+    pub fn new(capacity: usize, ttl: Duration) -> PuzzleCache<K, V> {
+        PuzzleCache {
+            entries: Mutex::new(IndexMap::new()),
+            capacity,
+            ttl,
+        }
+    }
+
+    // Concurrent calls for a missing key wait for the first one to make the value instead of
+    // all making it
+    pub async fn get_or_insert_with<F, Fut>(&self, key: K, f: F) -> V
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = V>,
+    {
+        let value = {
+            let mut entries = self.entries.lock().unwrap();
+            let entry = match entries.shift_remove(&key) {
+                Some(entry) if entry.created.elapsed() < self.ttl => entry,
+                _ => Entry {
+                    value: Arc::new(OnceCell::new()),
+                    created: Instant::now(),
+                },
+            };
+            let value = entry.value.clone();
+            entries.insert(key, entry);
+            while entries.len() > self.capacity {
+                entries.shift_remove_index(0);
+            }
+            value
+        };
+        value.get_or_init(f).await.clone()
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn evicts_least_recently_used() {
+        let cache = PuzzleCache::new(2, Duration::from_secs(60));
+        let made = AtomicUsize::new(0);
+        let get = |k: u32| {
+            let (cache, made) = (&cache, &made);
+            async move {
+                cache
+                    .get_or_insert_with(k, || async {
+                        made.fetch_add(1, Ordering::SeqCst);
+                        k * 10
+                    })
+                    .await
+            }
+        };
+        assert_eq!(get(1).await, 10);
+        assert_eq!(get(2).await, 20);
+        assert_eq!(get(1).await, 10);
+        assert_eq!(made.load(Ordering::SeqCst), 2);
+        // 2 is the least recently used now
+        get(3).await;
+        get(1).await;
+        assert_eq!(made.load(Ordering::SeqCst), 3);
+        get(2).await;
+        assert_eq!(made.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn expires_and_makes_once() {
+        let cache = PuzzleCache::new(8, Duration::ZERO);
+        let made = AtomicUsize::new(0);
+        let make = || async {
+            made.fetch_add(1, Ordering::SeqCst);
+            tokio::task::yield_now().await;
+        };
+        cache.get_or_insert_with(1, make).await;
+        cache.get_or_insert_with(1, make).await;
+        assert_eq!(made.load(Ordering::SeqCst), 2);
+
+        let cache = PuzzleCache::new(8, Duration::from_secs(60));
+        tokio::join!(
+            cache.get_or_insert_with(1, make),
+            cache.get_or_insert_with(1, make),
+            cache.get_or_insert_with(1, make),
+        );
+        assert_eq!(made.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::data::Ji;
+use crate::generate::{Generator, Puzzle};
+use crate::schedule;
+use crate::{ApiState, Difficulty, ReqMode, ResHint};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryStatus {
+    Draft,
+    Approved,
+    // Served as is, and no longer changed by generating again
+    Locked,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CalendarEntry {
+    pub status: EntryStatus,
+    pub difficulty: Difficulty,
+    pub puzzle: Puzzle,
+    pub score: f64,
+    pub near_misses: usize,
+}
+
+// Daily puzzles generated ahead of time for moderators to review, by date and mode name
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Calendar {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    pub days: BTreeMap<NaiveDate, BTreeMap<String, CalendarEntry>>,
+}
+
+impl Calendar {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Calendar> {
+        let path = path.into();
+        let mut calendar = match File::open(&path) {
+            Ok(file) => serde_json::from_reader(BufReader::new(file))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Calendar::default(),
+            Err(e) => return Err(e.into()),
+        };
+        calendar.path = path;
+        Ok(calendar)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let tmp = self.path.with_extension("tmp");
+        let file = File::create(&tmp)?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writer.flush()?;
+        fs::rename(tmp, &self.path)?;
+        Ok(())
+    }
+
+    pub fn locked(&self, date: NaiveDate, mode: &str) -> Option<&CalendarEntry> {
+        self.days
+            .get(&date)?
+            .get(mode)
+            .filter(|e| e.status == EntryStatus::Locked)
+    }
+
+    // Approved and locked entries have been reviewed, drafts have not
+    fn reviewed(&self, date: NaiveDate, mode: &str) -> bool {
+        self.days
+            .get(&date)
+            .and_then(|d| d.get(mode))
+            .is_some_and(|e| e.status != EntryStatus::Draft)
+    }
+
+    fn entry_mut(&mut self, date: NaiveDate, mode: &str) -> Result<&mut CalendarEntry> {
+        self.days
+            .get_mut(&date)
+            .and_then(|d| d.get_mut(mode))
+            .with_context(|| format!("No {mode} puzzle on {date}, generate it first"))
+    }
+
+    // Only days that are not locked yet may be changed
+    fn replace(&mut self, date: NaiveDate, mode: &str, entry: CalendarEntry) -> Result<()> {
+        if self.locked(date, mode).is_some() {
+            bail!("The {mode} puzzle on {date} is locked");
+        }
+        self.days
+            .entry(date)
+            .or_default()
+            .insert(mode.to_owned(), entry);
+        Ok(())
+    }
+}
+
+static USAGE: &str = "Usage: kanjidle-server schedule <command>
+    generate --from <date> --to <date>  Generate drafts for every mode, keeping reviewed days
+    show --from <date> --to <date>
+    approve <date> [mode]
+    reroll <date> <mode>
+    replace <date> <mode> <kanji>
+    lock <date>                         Serve the approved puzzles of every mode on the date";
+
+pub(crate) async fn run(state: &ApiState, args: &[String]) -> Result<()> {
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    match args[..] {
+        ["generate", "--from", from, "--to", to] => {
+            let scope = (None, state.schedule.clone());
+            let mut entries = vec![];
+            for date in dates(from, to)? {
+                let day = to_day(date);
+                for mode in ReqMode::ALL {
+                    if state.calendar.read().await.reviewed(date, mode.name()) {
+                        continue;
+                    }
+                    // The same puzzle as would be generated on the day, until it is changed
+                    let puzzle = state.daily_puzzle(&scope, day, mode).await;
+                    let difficulty = scope.1.day(day).difficulty;
+                    entries.push((date, mode, new_entry(state, day, difficulty, puzzle)));
+                }
+            }
+            let mut calendar = state.calendar.write().await;
+            for (date, mode, entry) in entries {
+                calendar.replace(date, mode.name(), entry)?;
+            }
+            calendar.save()?;
+            show(&calendar, from, to)
+        }
+        ["show", "--from", from, "--to", to] => show(&*state.calendar.read().await, from, to),
+        ["approve", date] | ["approve", date, _] => {
+            let date = parse_date(date)?;
+            let mut calendar = state.calendar.write().await;
+            let modes = match args[..] {
+                [_, _, mode] => vec![parse_mode(mode)?],
+                _ => ReqMode::ALL.to_vec(),
+            };
+            for mode in modes {
+                let entry = calendar.entry_mut(date, mode.name())?;
+                if entry.status == EntryStatus::Draft {
+                    entry.status = EntryStatus::Approved;
+                }
+            }
+            calendar.save()
+        }
+        ["reroll", date, mode] | ["replace", date, mode, _] => {
+            let date = parse_future_date(date)?;
+            let mode = parse_mode(mode)?;
+            let fixed = match args[..] {
+                [_, _, _, kanji] => Some(parse_kanji(kanji)?),
+                _ => None,
+            };
+            let day = to_day(date);
+            let puzzle = state.reroll_daily(day, mode, fixed).await;
+            if fixed.is_some_and(|ji| ji != puzzle.answer) {
+                bail!("No {} puzzle can be made with that answer", mode.name());
+            }
+            let difficulty = state.schedule.day(day).difficulty;
+            let entry = new_entry(state, day, difficulty, puzzle);
+            let mut calendar = state.calendar.write().await;
+            calendar.replace(date, mode.name(), entry)?;
+            calendar.save()?;
+            show(&calendar, &date.to_string(), &date.to_string())
+        }
+        ["lock", date] => {
+            let date = parse_future_date(date)?;
+            let mut calendar = state.calendar.write().await;
+            for mode in ReqMode::ALL {
+                if calendar.entry_mut(date, mode.name())?.status == EntryStatus::Draft {
+                    bail!("The {} puzzle on {date} is not approved", mode.name());
+                }
+            }
+            for entry in calendar.days.get_mut(&date).unwrap().values_mut() {
+                entry.status = EntryStatus::Locked;
+            }
+            calendar.save()?;
+            println!("Locked {date}, reload the calendar of a running server to serve it");
+            Ok(())
+        }
+        _ => bail!("{USAGE}"),
+    }
+}
+
+fn new_entry(
+    state: &ApiState,
+    day: DateTime<Utc>,
+    difficulty: Difficulty,
+    puzzle: Puzzle,
+) -> CalendarEntry {
+    let data = state.data_for(day);
+    let g = Generator::new(rand::thread_rng(), &data.kanji_data, &data.word_data);
+    let hints = puzzle.hints.iter().collect::<Vec<_>>();
+    CalendarEntry {
+        status: EntryStatus::Draft,
+        difficulty,
+        score: g.estimate_difficulty(&puzzle).total,
+        near_misses: g.solvability_report(puzzle.answer, &hints).decoys.len(),
+        puzzle,
+    }
+}
+
+fn show(calendar: &Calendar, from: &str, to: &str) -> Result<()> {
+    for date in dates(from, to)? {
+        for (mode, entry) in calendar.days.get(&date).into_iter().flatten() {
+            let hints = entry
+                .puzzle
+                .hints
+                .iter()
+                .map(|h| ResHint::new_from_hint(h).to_string())
+                .collect::<Vec<_>>()
+                .join(" ");
+            println!(
+                "{date} {mode:<13} {:<8} {:<8} score {:.2} near misses {:>2}  {} {hints}",
+                format!("{:?}", entry.status),
+                format!("{:?}", entry.difficulty),
+                entry.score,
+                entry.near_misses,
+                entry.puzzle.answer.0,
+            );
+        }
+    }
+    Ok(())
+}
+
+fn dates(from: &str, to: &str) -> Result<Vec<NaiveDate>> {
+    let (from, to) = (parse_date(from)?, parse_date(to)?);
+    Ok((0..=(to - from).num_days())
+        .map(|i| from + TimeDelta::days(i))
+        .collect())
+}
+
+fn to_day(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0).unwrap().and_utc()
+}
+
+fn parse_date(s: &str) -> Result<NaiveDate> {
+    s.parse()
+        .with_context(|| format!("Dates look like 2026-11-02, not {s:?}"))
+}
+
+// Days someone may already be playing keep the puzzle they were served
+fn parse_future_date(s: &str) -> Result<NaiveDate> {
+    let date = parse_date(s)?;
+    let today = schedule::latest_today();
+    if date <= today {
+        bail!("Only days after {today} can be changed, not {date}");
+    }
+    Ok(date)
+}
+
+fn parse_mode(s: &str) -> Result<ReqMode> {
+    ReqMode::ALL
+        .into_iter()
+        .find(|m| m.name() == s)
+        .with_context(|| format!("Unknown mode {s:?}"))
+}
+
+fn parse_kanji(s: &str) -> Result<Ji> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(Ji(c)),
+        _ => bail!("Expected a single kanji, not {s:?}"),
+    }
+}
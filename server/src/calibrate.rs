@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::data::Ji;
+use crate::generate::MetaHintKind;
+use crate::Difficulty;
+
+// Presets are left alone until a difficulty has been played this often
+pub static MIN_CALIBRATION_GAMES: usize = 50;
+static MAX_ADJUSTMENT: f64 = 2.0;
+// Results come from players, so a game counts as at most this many attempts
+pub static MAX_ATTEMPTS: usize = 100;
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
+pub struct Tally {
+    pub played: usize,
+    pub solved: usize,
+    pub attempts: usize,
+}
+
+impl Tally {
+    pub fn record(&mut self, solved: bool, attempts: usize) {
+        self.played += 1;
+        if solved {
+            self.solved += 1;
+        }
+        self.attempts = self.attempts.saturating_add(attempts.min(MAX_ATTEMPTS));
+    }
+
+    pub fn solve_rate(&self) -> f64 {
+        self.solved as f64 / self.played.max(1) as f64
+    }
+
+    pub fn mean_attempts(&self) -> f64 {
+        self.attempts as f64 / self.played.max(1) as f64
+    }
+
+    // Above 1 when players solve more often than the target and the preset should get harder,
+    // below 1 when it should get easier
+    pub fn adjustment(&self, target_solve_rate: f64) -> f64 {
+        if self.played < MIN_CALIBRATION_GAMES {
+            return 1.0;
+        }
+        let off = self.solve_rate() - target_solve_rate;
+        MAX_ADJUSTMENT
+            .powf(off * 4.0)
+            .clamp(1.0 / MAX_ADJUSTMENT, MAX_ADJUSTMENT)
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct Stats {
+    #[serde(default)]
+    pub by_difficulty: BTreeMap<Difficulty, Tally>,
+    #[serde(default)]
+    pub by_answer: BTreeMap<Ji, Tally>,
+    // Daily puzzles by the date the players saw, which depends on their timezone
+    #[serde(default)]
+    pub by_date: BTreeMap<NaiveDate, Tally>,
+    // The same split by mode name
+    #[serde(default)]
+    pub by_date_mode: BTreeMap<NaiveDate, BTreeMap<String, Tally>>,
+    // Served puzzles by id, which for daily puzzles is the same for every player
+    #[serde(default)]
+    pub by_puzzle: BTreeMap<u64, Tally>,
+    // How many players were given each meta hint of a served puzzle
+    #[serde(default)]
+    pub meta_hint_reveals_by_puzzle: BTreeMap<u64, BTreeMap<MetaHintKind, usize>>,
+}
+
+// What a result was for, beyond its answer
+#[derive(Debug, Default, Clone)]
+pub struct ResultOf {
+    pub difficulty: Option<Difficulty>,
+    pub date: Option<NaiveDate>,
+    pub mode: Option<&'static str>,
+    pub puzzle: Option<u64>,
+}
+
+impl Stats {
+    pub fn record(&mut self, answer: Ji, of: &ResultOf, solved: bool, attempts: usize) {
+        self.by_answer
+            .entry(answer)
+            .or_default()
+            .record(solved, attempts);
+        if let Some(difficulty) = of.difficulty {
+            self.by_difficulty
+                .entry(difficulty)
+                .or_default()
+                .record(solved, attempts);
+        }
+        if let Some(date) = of.date {
+            self.by_date
+                .entry(date)
+                .or_default()
+                .record(solved, attempts);
+            if let Some(mode) = of.mode {
+                self.by_date_mode
+                    .entry(date)
+                    .or_default()
+                    .entry(mode.to_owned())
+                    .or_default()
+                    .record(solved, attempts);
+            }
+        }
+        if let Some(id) = of.puzzle {
+            self.by_puzzle
+                .entry(id)
+                .or_default()
+                .record(solved, attempts);
+        }
+    }
+
+    // Kinds a player was given for the first time
+    pub fn record_reveals(&mut self, puzzle: u64, kinds: impl IntoIterator<Item = MetaHintKind>) {
+        let reveals = self.meta_hint_reveals_by_puzzle.entry(puzzle).or_default();
+        for kind in kinds {
+            *reveals.entry(kind).or_default() += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adjusts_towards_target() {
+        let mut tally = Tally::default();
+        for i in 0..MIN_CALIBRATION_GAMES - 1 {
+            tally.record(i % 10 != 0, 3);
+        }
+        assert_eq!(tally.adjustment(0.5), 1.0);
+
+        tally.record(true, 3);
+        assert_eq!(tally.mean_attempts(), 3.0);
+        assert!(tally.adjustment(0.5) > 1.0);
+        assert!(tally.adjustment(0.99) < 1.0);
+        assert_eq!(tally.adjustment(0.0), MAX_ADJUSTMENT);
+
+        tally.attempts = usize::MAX - 1;
+        tally.record(false, usize::MAX);
+        assert_eq!(tally.attempts, usize::MAX);
+    }
+
+    #[test]
+    fn splits_dailies_by_mode_and_puzzle() {
+        let mut stats = Stats::default();
+        let date = NaiveDate::from_ymd_opt(2026, 11, 2);
+        let of = |mode, puzzle| ResultOf {
+            difficulty: Some(Difficulty::Easy),
+            date,
+            mode: Some(mode),
+            puzzle: Some(puzzle),
+        };
+        stats.record(Ji('森'), &of("classic", 1), true, 2);
+        stats.record(Ji('森'), &of("classic", 1), false, 5);
+        stats.record(Ji('林'), &of("hidden", 2), true, 1);
+        let date = date.unwrap();
+        assert_eq!(stats.by_date[&date].played, 3);
+        assert_eq!(stats.by_date_mode[&date]["classic"].played, 2);
+        assert_eq!(stats.by_date_mode[&date]["hidden"].solved, 1);
+        assert_eq!(stats.by_puzzle[&1].attempts, 7);
+        assert_eq!(stats.by_puzzle[&2].played, 1);
+    }
+}
@@ -1,12 +1,14 @@
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     fmt::Display,
     fs::File,
     io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
     sync::LazyLock,
 };
 
 use anyhow::Result;
+use chrono::{NaiveDate, Utc};
 use indexmap::IndexMap;
 use itertools::{Either, EitherOrBoth, Itertools};
 use regex::Regex;
@@ -16,12 +18,29 @@ use serde_json::Deserializer;
 static ASSET_KANJIS: &str = "assets/wikipedia_kanjis.csv";
 static ASSET_KANJI_METAS: &str = "assets/kanjiten.jsonl";
 static ASSET_KANJI_RADICALS: &str = "assets/radicals.json";
+static ASSET_KANJI_COMPONENTS: &str = "assets/ids.txt";
 static ASSET_WORDS: &str = "assets/jpdb_words.csv";
 static ASSET_DICTIONARY: &str = "assets/jmdict.json";
+static ASSET_STROKE_PATHS: &str = "assets/kanjivg";
+// Versions of the source datasets by name, filled in by hand when downloading them
+static ASSET_SOURCES: &str = "assets/sources.json";
 
-static GENERATED_KANJIS: &str = "generated/kanjis.csv";
-static GENERATED_KANJI_METAS: &str = "generated/kanji_metas.json";
-static GENERATED_WORDS: &str = "generated/words.csv";
+pub static CURATION_BLOCKLIST: &str = "curation/blocklist.txt";
+pub static CURATION_ALLOWLIST: &str = "curation/allowlist.txt";
+
+pub static GENERATED_DIR: &str = "generated";
+// Earlier data builds, each in a folder of its own with the same files
+pub static GENERATED_ARCHIVE: &str = "generated/archive";
+
+static GENERATED_MANIFEST: &str = "manifest.json";
+static GENERATED_KANJIS: &str = "kanjis.csv";
+static GENERATED_KANJI_METAS: &str = "kanji_metas.json";
+static GENERATED_WORDS: &str = "words.csv";
+static GENERATED_STROKE_PATHS: &str = "stroke_paths.json";
+static GENERATED_THEMES: &str = "themes.json";
+
+// Builds from before manifests were written
+static LEGACY_DATA_VERSION: &str = "1";
 
 #[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(transparent)]
@@ -70,6 +89,42 @@ pub struct KanjiMeta {
     pub on: Vec<String>,
     pub kun: Vec<Kun>,
     pub variants: Vec<Ji>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub components: Option<Component>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub stroke_parts: Vec<StrokePart>,
+}
+
+// Strokes of a kanji split into its radical part and the rest, as listed by kanjiten
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StrokePart {
+    pub radical: String,
+    pub radical_strokes: usize,
+    pub other_strokes: usize,
+}
+
+// Ideographic description sequence as a tree, e.g. 学 is ⿱ of ⺍ over ⿱ of 冖 over 子
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Component {
+    Ji(Ji),
+    Compound(char, Vec<Component>),
+}
+
+impl Component {
+    pub fn leaves(&self) -> Vec<Ji> {
+        match self {
+            Component::Ji(ji) => vec![*ji],
+            Component::Compound(_, parts) => parts.iter().flat_map(|c| c.leaves()).collect(),
+        }
+    }
+
+    pub fn contains(&self, ji: Ji) -> bool {
+        match self {
+            Component::Ji(x) => *x == ji,
+            Component::Compound(_, parts) => parts.iter().any(|c| c.contains(ji)),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -94,7 +149,6 @@ struct RawMeta {
 }
 
 #[derive(Debug, Deserialize)]
-#[allow(dead_code)]
 struct RawStrokes(usize, Vec<(String, usize, usize)>);
 
 #[derive(Debug, Deserialize)]
@@ -125,10 +179,10 @@ pub struct KanjiData {
     pub kanji_metas: IndexMap<Ji, KanjiMeta>,
 }
 
-pub fn load_kanjis() -> Result<KanjiData> {
+pub fn load_kanjis(dir: &Path) -> Result<KanjiData> {
     if let (Ok(file_kanjis), Ok(file_kanji_metas)) = (
-        File::open(GENERATED_KANJIS),
-        File::open(GENERATED_KANJI_METAS),
+        File::open(dir.join(GENERATED_KANJIS)),
+        File::open(dir.join(GENERATED_KANJI_METAS)),
     ) {
         tracing::info!("Reading kanjis and kanji metas from generated files...");
 
@@ -183,6 +237,16 @@ pub fn load_kanjis() -> Result<KanjiData> {
                     },
                     level: raw.kanken,
                     stroke_count: raw.strokes.0,
+                    stroke_parts: raw
+                        .strokes
+                        .1
+                        .into_iter()
+                        .map(|(radical, radical_strokes, other_strokes)| StrokePart {
+                            radical,
+                            radical_strokes,
+                            other_strokes,
+                        })
+                        .collect(),
                     radical: radicals.get(&raw.radical[0]).unwrap().0.join("・"),
                     on: raw.on.into_iter().map(|on| on.0).collect(),
                     kun: raw
@@ -191,12 +255,19 @@ pub fn load_kanjis() -> Result<KanjiData> {
                         .map(|kun| Kun(kun.0 .0, kun.0 .1))
                         .collect(),
                     variants: raw.variants.into_iter().map(|v| v.0).collect(),
+                    components: None,
                 }),
             ))
         })
         .map(|x| x.map_err(anyhow::Error::from))
         .collect::<Result<IndexMap<_, _>>>()?;
 
+    let ids = load_ids()?;
+    let mut kanji_metas = kanji_metas;
+    for (ji, meta) in kanji_metas.iter_mut() {
+        meta.components = decompose(*ji, &ids, MAX_COMPONENT_DEPTH);
+    }
+
     let kanjis = kanjis
         .into_iter()
         .filter(|k| kanji_metas.contains_key(&k.0))
@@ -208,7 +279,7 @@ pub fn load_kanjis() -> Result<KanjiData> {
         .collect::<IndexMap<_, _>>();
 
     tracing::info!("Writing generated kanjis file...");
-    let file = File::create(GENERATED_KANJIS)?;
+    let file = File::create(dir.join(GENERATED_KANJIS))?;
     let mut writer = csv::Writer::from_writer(BufWriter::new(file));
     for kanji in kanjis.values() {
         writer.serialize(kanji)?;
@@ -216,7 +287,7 @@ pub fn load_kanjis() -> Result<KanjiData> {
     writer.flush()?;
 
     tracing::info!("Writing generated kanji metas file...");
-    let file = File::create(GENERATED_KANJI_METAS)?;
+    let file = File::create(dir.join(GENERATED_KANJI_METAS))?;
     let mut writer = BufWriter::new(file);
     serde_json::to_writer(&mut writer, &kanji_metas)?;
     writer.flush()?;
@@ -227,7 +298,146 @@ pub fn load_kanjis() -> Result<KanjiData> {
     })
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+// Kanji by semantic category, kept by hand in the generated folder
+pub type Themes = IndexMap<String, Vec<Ji>>;
+
+pub fn load_themes(dir: &Path, kanji_data: &KanjiData) -> Result<Themes> {
+    let file = match File::open(dir.join(GENERATED_THEMES)) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(IndexMap::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let raw: IndexMap<String, String> = serde_json::from_reader(BufReader::new(file))?;
+    Ok(raw
+        .into_iter()
+        .map(|(name, ks)| {
+            let ks = ks
+                .chars()
+                .map(Ji)
+                .filter(|ji| kanji_data.kanjis.contains_key(ji))
+                .collect();
+            (name, ks)
+        })
+        .collect())
+}
+
+// SVG path data of every stroke of a kanji, in stroke order
+pub type StrokePaths = IndexMap<Ji, Vec<String>>;
+
+pub static KANJIVG_PATH: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"<path id="kvg:[0-9a-f]+-s\d+"[^>]*?\sd="([^"]+)""#).unwrap());
+
+// KanjiVG is optional, without it there are no stroke paths to show
+pub fn load_stroke_paths(kanji_data: &KanjiData) -> Result<StrokePaths> {
+    let generated = Path::new(GENERATED_DIR).join(GENERATED_STROKE_PATHS);
+    if let Ok(file) = File::open(&generated) {
+        tracing::info!("Reading stroke paths from generated file...");
+        return Ok(serde_json::from_reader(BufReader::new(file))?);
+    }
+
+    let Ok(dir) = std::fs::read_dir(ASSET_STROKE_PATHS) else {
+        tracing::warn!("No stroke paths found, continuing without them");
+        return Ok(IndexMap::new());
+    };
+    let mut paths = StrokePaths::new();
+    for entry in dir {
+        let path = entry?.path();
+        // Files are named after the code point, variants have a suffix and are skipped
+        let Some(ji) = path
+            .file_stem()
+            .and_then(|x| x.to_str())
+            .and_then(|x| u32::from_str_radix(x, 16).ok())
+            .and_then(char::from_u32)
+            .map(Ji)
+        else {
+            continue;
+        };
+        if !kanji_data.kanjis.contains_key(&ji) {
+            continue;
+        }
+        let svg = std::fs::read_to_string(&path)?;
+        let strokes = KANJIVG_PATH
+            .captures_iter(&svg)
+            .map(|c| c.extract::<1>().1[0].to_owned())
+            .collect();
+        paths.insert(ji, strokes);
+    }
+    paths.sort_keys();
+
+    tracing::info!("Writing generated stroke paths file...");
+    let file = File::create(generated)?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer(&mut writer, &paths)?;
+    writer.flush()?;
+
+    Ok(paths)
+}
+
+static MAX_COMPONENT_DEPTH: usize = 8;
+
+// Lines look like "U+5B66\t学\t⿱⺍冖子[J]\t...", only the first sequence is kept
+fn load_ids() -> Result<HashMap<Ji, String>> {
+    let file = File::open(ASSET_KANJI_COMPONENTS)?;
+    let mut ids = HashMap::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.starts_with('#') {
+            continue;
+        }
+        let mut cols = line.split('\t').skip(1);
+        let (Some(ji), Some(seq)) = (cols.next().and_then(extract_only_char), cols.next()) else {
+            continue;
+        };
+        let seq = seq.split('[').next().unwrap_or_default();
+        ids.insert(Ji(ji), seq.to_owned());
+    }
+    Ok(ids)
+}
+
+fn decompose(ji: Ji, ids: &HashMap<Ji, String>, depth: usize) -> Option<Component> {
+    let seq = ids.get(&ji)?;
+    let mut chars = seq.chars();
+    let tree = parse_ids(&mut chars)?;
+    if chars.next().is_some() {
+        return None;
+    }
+    Some(expand(tree, ji, ids, depth))
+}
+
+// Leaves that have a decomposition of their own are replaced by it
+fn expand(c: Component, parent: Ji, ids: &HashMap<Ji, String>, depth: usize) -> Component {
+    match c {
+        Component::Ji(x) if x != parent && depth > 0 => match decompose(x, ids, depth - 1) {
+            Some(sub @ Component::Compound(..)) => sub,
+            _ => Component::Ji(x),
+        },
+        Component::Ji(x) => Component::Ji(x),
+        Component::Compound(op, parts) => Component::Compound(
+            op,
+            parts
+                .into_iter()
+                .map(|p| expand(p, parent, ids, depth))
+                .collect(),
+        ),
+    }
+}
+
+fn parse_ids(chars: &mut impl Iterator<Item = char>) -> Option<Component> {
+    let c = chars.next()?;
+    let arity = match c {
+        '⿰' | '⿱' | '⿴' | '⿵' | '⿶' | '⿷' | '⿸' | '⿹' | '⿺' | '⿻' => 2,
+        '⿲' | '⿳' => 3,
+        // Components without an encoding of their own cannot be matched against anything
+        '&' | '？' | '?' => return None,
+        _ => return Some(Component::Ji(Ji(c))),
+    };
+    let parts = (0..arity)
+        .map(|_| parse_ids(chars))
+        .collect::<Option<Vec<_>>>()?;
+    Some(Component::Compound(c, parts))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 pub enum Loc {
     L,
     R,
@@ -253,11 +463,53 @@ static ESTIMATED_JMDICT_SIZE: usize = 1_000_000;
 
 pub static MAX_WORD_RANK: usize = 400_000;
 
+pub static JMDICT_SENSE_PLACE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#""sense"(.+)"#).unwrap());
+
+pub static JMDICT_MISC: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#""misc":\[(.*?)\]"#).unwrap());
+
+pub static JMDICT_TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#""(\w+)""#).unwrap());
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Word {
     pub text: String,
     pub reading: String,
     pub rank: usize,
+    // JMdict misc tags shared by every sense, separated by spaces
+    #[serde(default)]
+    pub tags: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WordTag {
+    Place,
+    Surname,
+    Arch,
+    Obs,
+    Rare,
+    Sens,
+}
+
+impl WordTag {
+    pub fn from_code(code: &str) -> Option<WordTag> {
+        Some(match code {
+            "place" => WordTag::Place,
+            "surname" => WordTag::Surname,
+            "arch" => WordTag::Arch,
+            "obs" => WordTag::Obs,
+            "rare" => WordTag::Rare,
+            "sens" => WordTag::Sens,
+            _ => return None,
+        })
+    }
+
+    pub fn parse_list(tags: &str) -> Vec<WordTag> {
+        tags.split_whitespace()
+            .filter_map(WordTag::from_code)
+            .collect()
+    }
 }
 
 #[derive(Debug)]
@@ -266,6 +518,133 @@ pub struct Compound2 {
     pub a: Ji,
     pub b: Ji,
     pub irregularness: f64,
+    pub tags: Vec<WordTag>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CurationReason {
+    Vulgar,
+    Sensitive,
+    Archaic,
+    Obscure,
+    NotAWord,
+    Other,
+}
+
+impl std::str::FromStr for CurationReason {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "vulgar" => CurationReason::Vulgar,
+            "sensitive" => CurationReason::Sensitive,
+            "archaic" => CurationReason::Archaic,
+            "obscure" => CurationReason::Obscure,
+            "not_a_word" => CurationReason::NotAWord,
+            "other" => CurationReason::Other,
+            _ => anyhow::bail!("Unknown curation reason {s:?}"),
+        })
+    }
+}
+
+impl Display for CurationReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            CurationReason::Vulgar => "vulgar",
+            CurationReason::Sensitive => "sensitive",
+            CurationReason::Archaic => "archaic",
+            CurationReason::Obscure => "obscure",
+            CurationReason::NotAWord => "not_a_word",
+            CurationReason::Other => "other",
+        };
+        write!(f, "{s}")
+    }
+}
+
+// Hand-picked words that are never used, or always kept, as hints. A single kanji in the
+// blocklist blocks every compound with it, unless the compound is in the allowlist.
+#[derive(Debug, Default)]
+pub struct Curation {
+    pub blocked: HashMap<String, CurationReason>,
+    pub allowed: HashMap<String, CurationReason>,
+    // Blocks that also apply to the current build, from their date on
+    pub blocked_since: HashMap<String, NaiveDate>,
+}
+
+impl Curation {
+    pub fn load() -> Result<Curation> {
+        let blocklist = read_curation_list(CURATION_BLOCKLIST)?;
+        Ok(Curation {
+            blocked_since: blocklist
+                .iter()
+                .filter_map(|(text, (_, since))| Some((text.clone(), (*since)?)))
+                .collect(),
+            blocked: blocklist
+                .into_iter()
+                .map(|(text, (reason, _))| (text, reason))
+                .collect(),
+            allowed: read_curation_list(CURATION_ALLOWLIST)?
+                .into_iter()
+                .map(|(text, (reason, _))| (text, reason))
+                .collect(),
+        })
+    }
+
+    // First day a word of an already built file is blocked on, if it is
+    pub fn blocked_since(&self, text: &str) -> Option<NaiveDate> {
+        if self.allowed.contains_key(text) {
+            return None;
+        }
+        std::iter::once(text.to_owned())
+            .chain(text.chars().map(|c| c.to_string()))
+            .filter_map(|t| self.blocked_since.get(&t).copied())
+            .min()
+    }
+
+    pub fn is_blocked(&self, text: &str) -> bool {
+        !self.allowed.contains_key(text)
+            && (self.blocked.contains_key(text)
+                || text
+                    .chars()
+                    .any(|c| self.blocked.contains_key(&c.to_string())))
+    }
+}
+
+// Lines look like "配中 not_a_word" or "配中 not_a_word 2026-11-02", anything after a # is a
+// comment
+fn read_curation_list(path: &str) -> Result<HashMap<String, (CurationReason, Option<NaiveDate>)>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut list = HashMap::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        let line = line.split('#').next().unwrap_or_default();
+        let (text, reason, since) = match line.split_whitespace().collect_vec()[..] {
+            [text, reason] => (text, reason, None),
+            [text, reason, since] => (text, reason, Some(since.parse()?)),
+            _ => {
+                if !line.trim().is_empty() {
+                    tracing::warn!("Skipping malformed line {line:?} in {path}");
+                }
+                continue;
+            }
+        };
+        list.insert(text.to_owned(), (reason.parse()?, since));
+    }
+    Ok(list)
+}
+
+pub fn append_to_blocklist(text: &str, reason: CurationReason, since: NaiveDate) -> Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(CURATION_BLOCKLIST)?;
+    writeln!(file, "{text} {reason} {since}")?;
+    Ok(())
 }
 
 #[derive(Debug)]
@@ -273,9 +652,47 @@ pub struct WordData {
     pub twos: IndexMap<String, Compound2>,
 }
 
-pub fn load_words(kanji_data: &KanjiData) -> Result<WordData> {
-    let twos = if let Ok(file) = File::open(GENERATED_WORDS) {
+impl WordData {
+    // Compounds with the kanji on the given side, most common first, leaving out blocked ones
+    pub fn compounds_of<'a>(
+        &'a self,
+        ji: Ji,
+        loc: Loc,
+        blocked: &'a HashMap<String, NaiveDate>,
+    ) -> impl Iterator<Item = &'a Compound2> + 'a {
+        self.twos
+            .values()
+            .filter(move |two| {
+                !blocked.contains_key(&two.word.text)
+                    && match loc {
+                        Loc::L => two.a == ji,
+                        Loc::R => two.b == ji,
+                    }
+            })
+            .sorted_by_key(|two| two.word.rank)
+    }
+
+    // Other compounds sharing the kanji on the given side of the word
+    pub fn siblings_of<'a>(
+        &'a self,
+        two: &'a Compound2,
+        loc: Loc,
+        blocked: &'a HashMap<String, NaiveDate>,
+    ) -> impl Iterator<Item = &'a Compound2> + 'a {
+        let ji = match loc {
+            Loc::L => two.a,
+            Loc::R => two.b,
+        };
+        self.compounds_of(ji, loc, blocked)
+            .filter(move |x| x.word.text != two.word.text)
+    }
+}
+
+pub fn load_words(dir: &Path, kanji_data: &KanjiData, curation: &Curation) -> Result<WordData> {
+    let twos = if let Ok(file) = File::open(dir.join(GENERATED_WORDS)) {
         tracing::info!("Reading words from generated file...");
+        // Changes to the lists since the file was generated wait for the next build, except
+        // for dated blocks, so the puzzles of past days stay the same
         let mut reader = csv::Reader::from_reader(file);
         reader.deserialize::<Word>().process_results(|iter| {
             iter.filter_map(|word| {
@@ -287,6 +704,7 @@ pub fn load_words(kanji_data: &KanjiData) -> Result<WordData> {
                     Compound2 {
                         a,
                         b,
+                        tags: WordTag::parse_list(&word.tags),
                         irregularness: irregularness(
                             &word.text,
                             kanji_data.kanji_metas.get(&a)?,
@@ -328,12 +746,16 @@ pub fn load_words(kanji_data: &KanjiData) -> Result<WordData> {
                         }
                     }
 
-                    Some(xs.into_iter().flat_map(|k| {
+                    let tags = jmdict_tags(&str);
+
+                    Some(xs.into_iter().flat_map(move |k| {
+                        let tags = tags.clone();
                         k.1.into_iter().filter_map(move |r| {
                             let word = Word {
                                 rank: MAX_WORD_RANK,
                                 text: k.0.clone(),
                                 reading: r,
+                                tags: tags.clone(),
                             };
                             Some((k.0.clone(), extract_compound2(word, kanji_data)?))
                         })
@@ -351,16 +773,14 @@ pub fn load_words(kanji_data: &KanjiData) -> Result<WordData> {
         for x in rdr.records() {
             let x = x?;
             let two = (|| {
+                let text = x.get(0)?;
                 let word = Word {
-                    text: x.get(0)?.into(),
+                    text: text.into(),
                     reading: x.get(1)?.into(),
                     rank: x.get(2)?.parse().ok()?,
+                    tags: known_words.get(text)?.word.tags.clone(),
                 };
-                if !known_words.contains_key(&word.text) {
-                    None
-                } else {
-                    extract_compound2(word, kanji_data)
-                }
+                extract_compound2(word, kanji_data)
             })();
             if let Some(two) = two {
                 if !twos.contains_key(&two.word.text) {
@@ -374,8 +794,17 @@ pub fn load_words(kanji_data: &KanjiData) -> Result<WordData> {
             }
         }
 
+        twos.retain(|text, _| !curation.is_blocked(text));
+        // Allowlisted words are kept whatever their tags say
+        for (text, two) in twos.iter_mut() {
+            if curation.allowed.contains_key(text) {
+                two.word.tags.clear();
+                two.tags.clear();
+            }
+        }
+
         tracing::info!("Writing generated words file...");
-        let file = File::create(GENERATED_WORDS)?;
+        let file = File::create(dir.join(GENERATED_WORDS))?;
         let mut writer = csv::Writer::from_writer(BufWriter::new(file));
         for two in twos.values() {
             writer.serialize(&two.word)?;
@@ -388,6 +817,204 @@ pub fn load_words(kanji_data: &KanjiData) -> Result<WordData> {
     Ok(WordData { twos })
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DataManifest {
+    pub version: String,
+    // First day of daily puzzles made from this build, earlier days use an archived one
+    #[serde(default)]
+    pub since: Option<NaiveDate>,
+    #[serde(default)]
+    pub sources: IndexMap<String, String>,
+}
+
+impl DataManifest {
+    fn load(dir: &Path) -> Result<DataManifest> {
+        match File::open(dir.join(GENERATED_MANIFEST)) {
+            Ok(file) => Ok(serde_json::from_reader(BufReader::new(file))?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(DataManifest {
+                version: LEGACY_DATA_VERSION.to_owned(),
+                since: None,
+                sources: IndexMap::new(),
+            }),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    // A fresh build is versioned by when it was made, and only used from the day after, so
+    // no day already being played changes
+    fn write_new(dir: &Path) -> Result<DataManifest> {
+        let sources = match File::open(ASSET_SOURCES) {
+            Ok(file) => serde_json::from_reader(BufReader::new(file))?,
+            Err(_) => IndexMap::new(),
+        };
+        let manifest = DataManifest {
+            version: Utc::now().format("%Y%m%d%H%M%S").to_string(),
+            since: crate::schedule::latest_today().succ_opt(),
+            sources,
+        };
+        tracing::info!("Writing data manifest for version {}...", manifest.version);
+        let file = File::create(dir.join(GENERATED_MANIFEST))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, &manifest)?;
+        writer.flush()?;
+        Ok(manifest)
+    }
+}
+
+#[derive(Debug)]
+pub struct DataSet {
+    pub manifest: DataManifest,
+    pub kanji_data: KanjiData,
+    pub word_data: WordData,
+    pub themes: Themes,
+}
+
+impl DataSet {
+    // Parts that builds from before they were added do not have
+    pub fn missing_parts(&self) -> Vec<&'static str> {
+        let metas = self.kanji_data.kanji_metas.values();
+        let mut missing = vec![];
+        if metas.clone().all(|m| m.components.is_none()) {
+            missing.push("components");
+        }
+        if metas.clone().all(|m| m.stroke_parts.is_empty()) {
+            missing.push("stroke parts");
+        }
+        if self.word_data.twos.values().all(|w| w.word.tags.is_empty()) {
+            missing.push("word tags");
+        }
+        missing
+    }
+}
+
+pub fn load_data_set(dir: &Path, curation: &Curation) -> Result<DataSet> {
+    let built = dir.join(GENERATED_KANJIS).exists() && dir.join(GENERATED_WORDS).exists();
+    let kanji_data = load_kanjis(dir)?;
+    let word_data = load_words(dir, &kanji_data, curation)?;
+    let themes = load_themes(dir, &kanji_data)?;
+    let manifest = if built {
+        DataManifest::load(dir)?
+    } else {
+        DataManifest::write_new(dir)?
+    };
+    Ok(DataSet {
+        manifest,
+        kanji_data,
+        word_data,
+        themes,
+    })
+}
+
+// The newest build that had started by the date, the current one is used for dates before
+// every build
+pub fn current_on<'a>(
+    current: &'a DataSet,
+    archive: &'a [DataSet],
+    date: NaiveDate,
+) -> &'a DataSet {
+    std::iter::once(current)
+        .chain(archive.iter().rev())
+        .find(|d| d.manifest.since.is_none_or(|since| since <= date))
+        .unwrap_or(current)
+}
+
+// Archived builds, oldest first. Their words were curated while they were current, and are
+// left that way so their puzzles do not change.
+pub fn load_archive() -> Result<Vec<DataSet>> {
+    let Ok(dir) = std::fs::read_dir(GENERATED_ARCHIVE) else {
+        return Ok(vec![]);
+    };
+    let mut sets = vec![];
+    for entry in dir {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        anyhow::ensure!(
+            path.join(GENERATED_KANJIS).exists() && path.join(GENERATED_WORDS).exists(),
+            "Archived data in {path:?} is incomplete"
+        );
+        let set = load_data_set(&path, &Curation::default())?;
+        tracing::info!("Loaded archived data version {}", set.manifest.version);
+        sets.push(set);
+    }
+    sets.sort_by_key(|s| s.manifest.since);
+    Ok(sets)
+}
+
+static REBUILD_ASSETS: [&str; 7] = [
+    ASSET_KANJIS,
+    ASSET_KANJI_METAS,
+    ASSET_KANJI_RADICALS,
+    ASSET_KANJI_COMPONENTS,
+    ASSET_WORDS,
+    ASSET_DICTIONARY,
+    ASSET_STROKE_PATHS,
+];
+
+// Moves the current build to the archive so its days keep their puzzles, then builds a new one
+// from the assets, stroke paths included. Themes are kept by hand so the archive gets a copy.
+pub fn rebuild(curation: &Curation) -> Result<DataSet> {
+    let missing = REBUILD_ASSETS
+        .into_iter()
+        .filter(|x| !Path::new(x).exists())
+        .collect_vec();
+    anyhow::ensure!(missing.is_empty(), "Missing assets {}", missing.join(", "));
+
+    let generated = Path::new(GENERATED_DIR);
+    let version = DataManifest::load(generated)?.version;
+    let archived = Path::new(GENERATED_ARCHIVE).join(&version);
+    anyhow::ensure!(
+        !archived.exists(),
+        "Data version {version} is already archived in {archived:?}"
+    );
+    tracing::info!("Archiving data version {version} to {archived:?}...");
+    std::fs::create_dir_all(&archived)?;
+    for name in [
+        GENERATED_MANIFEST,
+        GENERATED_KANJIS,
+        GENERATED_KANJI_METAS,
+        GENERATED_WORDS,
+        GENERATED_STROKE_PATHS,
+    ] {
+        let path = generated.join(name);
+        if path.exists() {
+            std::fs::rename(path, archived.join(name))?;
+        }
+    }
+    if generated.join(GENERATED_THEMES).exists() {
+        std::fs::copy(
+            generated.join(GENERATED_THEMES),
+            archived.join(GENERATED_THEMES),
+        )?;
+    }
+
+    let set = load_data_set(generated, curation)?;
+    load_stroke_paths(&set.kanji_data)?;
+    Ok(set)
+}
+
+static USAGE: &str = "Usage: kanjidle-server data <command>
+    rebuild  Archive the current build and build a new one from the assets";
+
+pub fn run(args: &[String]) -> Result<()> {
+    let args = args.iter().map(String::as_str).collect_vec();
+    match args[..] {
+        ["rebuild"] => {
+            let set = rebuild(&Curation::load()?)?;
+            println!(
+                "Built data version {}, used from {}",
+                set.manifest.version,
+                set.manifest
+                    .since
+                    .map_or("now".to_owned(), |x| x.to_string())
+            );
+            Ok(())
+        }
+        _ => anyhow::bail!("{USAGE}"),
+    }
+}
+
 fn extract_compound2(word: Word, kanji_data: &KanjiData) -> Option<Compound2> {
     let (_, [a, b]) = TWO_KANJI.captures(&word.text)?.extract();
     if a == b || b == "々" {
@@ -398,6 +1025,7 @@ fn extract_compound2(word: Word, kanji_data: &KanjiData) -> Option<Compound2> {
         Some(Compound2 {
             a,
             b,
+            tags: WordTag::parse_list(&word.tags),
             irregularness: irregularness(
                 &word.text,
                 kanji_data.kanji_metas.get(&a)?,
@@ -408,6 +1036,29 @@ fn extract_compound2(word: Word, kanji_data: &KanjiData) -> Option<Compound2> {
     }
 }
 
+// Only tags that every sense has describe the word itself, a common word with one archaic
+// sense is not archaic
+fn jmdict_tags(entry: &str) -> String {
+    let Some((_, [senses])) = JMDICT_SENSE_PLACE.captures(entry).map(|c| c.extract()) else {
+        return String::new();
+    };
+    JMDICT_MISC
+        .captures_iter(senses)
+        .map(|c| {
+            let (_, [misc]) = c.extract();
+            JMDICT_TAG
+                .captures_iter(misc)
+                .map(|t| t.extract::<1>().1[0])
+                .filter(|t| WordTag::from_code(t).is_some())
+                .collect::<HashSet<_>>()
+        })
+        .reduce(|a, b| a.intersection(&b).copied().collect())
+        .unwrap_or_default()
+        .into_iter()
+        .sorted()
+        .join(" ")
+}
+
 // Very rudimentary method of doing this but it should be fine
 fn irregularness(word: &str, a: &KanjiMeta, b: &KanjiMeta) -> f64 {
     let readings_a =
@@ -481,3 +1132,174 @@ where
 fn extract_only_char(x: &str) -> Option<char> {
     x.chars().exactly_one().ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decomposes_nested_ids() {
+        let ids = [('学', "⿱⺍⿱冖子"), ('字', "⿱宀子"), ('⺍', "⺍")]
+            .into_iter()
+            .map(|(k, v)| (Ji(k), v.to_owned()))
+            .collect::<HashMap<_, _>>();
+        let gaku = decompose(Ji('学'), &ids, MAX_COMPONENT_DEPTH).unwrap();
+        assert_eq!(gaku.leaves(), [Ji('⺍'), Ji('冖'), Ji('子')]);
+        assert!(gaku.contains(Ji('子')));
+        assert_eq!(
+            serde_json::to_string(&gaku).unwrap(),
+            r#"["⿱",["⺍",["⿱",["冖","子"]]]]"#
+        );
+        assert_eq!(decompose(Ji('⺍'), &ids, 1), Some(Component::Ji(Ji('⺍'))));
+        assert_eq!(decompose(Ji('国'), &ids, 1), None);
+    }
+
+    #[test]
+    fn curation_blocks_words_and_kanji() {
+        let curation = Curation {
+            blocked: [
+                ("配中", CurationReason::NotAWord),
+                ("糞", CurationReason::Vulgar),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_owned(), v))
+            .collect(),
+            allowed: [("糞便", CurationReason::Other)]
+                .into_iter()
+                .map(|(k, v)| (k.to_owned(), v))
+                .collect(),
+            blocked_since: [("糞", NaiveDate::from_ymd_opt(2026, 11, 2).unwrap())]
+                .into_iter()
+                .map(|(k, v)| (k.to_owned(), v))
+                .collect(),
+        };
+        assert!(curation.is_blocked("配中"));
+        assert!(curation.is_blocked("馬糞"));
+        assert!(!curation.is_blocked("糞便"));
+        assert!(!curation.is_blocked("中心"));
+        assert_eq!(curation.blocked_since("配中"), None);
+        assert_eq!(
+            curation.blocked_since("馬糞"),
+            NaiveDate::from_ymd_opt(2026, 11, 2)
+        );
+        assert_eq!(curation.blocked_since("糞便"), None);
+        assert_eq!(
+            "not_a_word".parse::<CurationReason>().unwrap(),
+            CurationReason::NotAWord
+        );
+        assert_eq!(CurationReason::NotAWord.to_string(), "not_a_word");
+    }
+
+    fn two(text: &str, rank: usize) -> (String, Compound2) {
+        let mut chars = text.chars();
+        let compound = Compound2 {
+            a: Ji(chars.next().unwrap()),
+            b: Ji(chars.next().unwrap()),
+            word: Word {
+                text: text.to_owned(),
+                reading: String::new(),
+                rank,
+                tags: String::new(),
+            },
+            irregularness: 0.0,
+            tags: vec![],
+        };
+        (text.to_owned(), compound)
+    }
+
+    #[test]
+    fn looks_up_common_unblocked_compounds() {
+        let word_data = WordData {
+            twos: [("学生", 20), ("学校", 10), ("学会", 30), ("大学", 5)]
+                .into_iter()
+                .map(|(text, rank)| two(text, rank))
+                .collect(),
+        };
+        let blocked = [(
+            "学会".to_owned(),
+            NaiveDate::from_ymd_opt(2026, 11, 2).unwrap(),
+        )]
+        .into_iter()
+        .collect();
+        let texts = |xs: &mut dyn Iterator<Item = &Compound2>| {
+            xs.map(|x| x.word.text.clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            texts(&mut word_data.compounds_of(Ji('学'), Loc::L, &blocked)),
+            ["学校", "学生"]
+        );
+        assert_eq!(
+            texts(&mut word_data.compounds_of(Ji('学'), Loc::R, &blocked)),
+            ["大学"]
+        );
+        let gakusei = &word_data.twos["学生"];
+        assert_eq!(
+            texts(&mut word_data.siblings_of(gakusei, Loc::L, &blocked)),
+            ["学校"]
+        );
+        assert!(word_data
+            .siblings_of(gakusei, Loc::R, &blocked)
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn uses_the_build_current_on_the_day() {
+        let date = |d| NaiveDate::from_ymd_opt(2026, 11, d).unwrap();
+        let set = |version: &str, since| DataSet {
+            manifest: DataManifest {
+                version: version.to_owned(),
+                since,
+                sources: IndexMap::new(),
+            },
+            kanji_data: KanjiData {
+                kanjis: IndexMap::new(),
+                kanji_metas: IndexMap::new(),
+            },
+            word_data: WordData {
+                twos: IndexMap::new(),
+            },
+            themes: IndexMap::new(),
+        };
+        let archive = [set("1", None), set("2", Some(date(2)))];
+        let current = set("3", Some(date(10)));
+        let version = |d| &current_on(&current, &archive, date(d)).manifest.version;
+        assert_eq!(version(1), "1");
+        assert_eq!(version(2), "2");
+        assert_eq!(version(9), "2");
+        assert_eq!(version(10), "3");
+        assert_eq!(version(30), "3");
+        assert_eq!(current_on(&current, &[], date(1)).manifest.version, "3");
+    }
+
+    #[test]
+    fn keeps_tags_of_every_sense() {
+        let entry = r#"{"kanji":[{"text":"御座"}],"kana":[],"sense":[{"misc":["arch","hon"]},{"misc":["arch","rare"]}]}"#;
+        assert_eq!(jmdict_tags(entry), "arch");
+        assert_eq!(
+            WordTag::parse_list("arch obs"),
+            [WordTag::Arch, WordTag::Obs]
+        );
+        let entry = r#"{"kanji":[],"kana":[],"sense":[{"misc":["arch"]},{"misc":[]}]}"#;
+        assert_eq!(jmdict_tags(entry), "");
+    }
+
+    #[test]
+    fn extracts_kanjivg_strokes() {
+        let svg = r#"<g id="kvg:04e8c" kvg:element="二">
+<path id="kvg:04e8c-s1" kvg:type="㇐" d="M31.5,25.5c1.6,0.4,3.6,0.6,5.5,0.4"/>
+<path id="kvg:04e8c-s2" kvg:type="㇐" d="M12.6,79.4c2.8,0.7,5.9,0.8,8.7,0.5"/>
+</g>"#;
+        let strokes = KANJIVG_PATH
+            .captures_iter(svg)
+            .map(|c| c.extract::<1>().1[0])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            strokes,
+            [
+                "M31.5,25.5c1.6,0.4,3.6,0.6,5.5,0.4",
+                "M12.6,79.4c2.8,0.7,5.9,0.8,8.7,0.5"
+            ]
+        );
+    }
+}
@@ -1,9 +1,14 @@
+use std::collections::HashSet;
 use std::time::Instant;
 
 use itertools::Itertools;
 use ordered_float::OrderedFloat;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
 
-use crate::data::{Ji, KanjiClass, KanjiData, Loc, WordData, MAX_KANJI_RANK, MAX_WORD_RANK};
+use crate::data::{
+    Ji, KanjiClass, KanjiData, KanjiMeta, Loc, WordData, WordTag, MAX_KANJI_RANK, MAX_WORD_RANK,
+};
 
 #[derive(Debug)]
 pub struct PuzzleOptions {
@@ -12,12 +17,17 @@ pub struct PuzzleOptions {
     pub max_kanji_class: KanjiClass,
     pub rare_kanji_bias: f64,
     pub fixed: Option<Ji>,
+    pub exclude: HashSet<Ji>, // Only chosen once every other kanji has failed
+    pub theme: Option<HashSet<Ji>>, // Preferred over others, within excluded and the rest alike
 
     // Hint picking options
     pub min_word_rarity: usize,
     pub max_word_rarity: usize,
     pub min_word_kanji_class: KanjiClass, // Applies to words as well
     pub max_word_kanji_class: KanjiClass, // Applies to words as well
+    pub exclude_words: HashSet<String>,   // Blocked after the words were loaded
+    pub include_word_tags: HashSet<WordTag>, // Words need one of these, unless it is empty
+    pub exclude_word_tags: HashSet<WordTag>, // Words may have none of these
 
     // Hint ordering options
     pub irregular_hint_bias: f64,
@@ -27,9 +37,22 @@ pub struct PuzzleOptions {
     // Puzzle size options
     pub num_hints: usize,
     pub guarantee_answer_by: usize,
+    pub minimize_hints: bool, // Keeps only the fewest hints that still make the answer unique
+
+    // Presentation options
+    pub hint_ordering: HintOrdering,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HintOrdering {
+    // In the order they were picked, as daily puzzles were before hints were ordered
+    AsChosen,
+    HardToEasy,
+    EasyToHard,
+    Random,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Hint {
     pub answer: Ji,
     pub answer_location: Loc,
@@ -38,13 +61,64 @@ pub struct Hint {
     pub irregularness: f64,
 }
 
-#[derive(Debug)]
+// Hints about the answer itself, in the order they are meant to be revealed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetaHintKind {
+    Radical,
+    StrokeCount,
+    OnReading,
+}
+
+impl MetaHintKind {
+    pub const ALL: [MetaHintKind; 3] = [
+        MetaHintKind::Radical,
+        MetaHintKind::StrokeCount,
+        MetaHintKind::OnReading,
+    ];
+
+    pub fn reveal(&self, meta: &KanjiMeta) -> Option<String> {
+        match self {
+            MetaHintKind::Radical => Some(meta.radical.clone()),
+            MetaHintKind::StrokeCount => Some(meta.stroke_count.to_string()),
+            MetaHintKind::OnReading => meta.on.first().cloned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Puzzle {
     pub answer: Ji,
     pub hints: Vec<Hint>,
     pub extra_hints: Vec<Hint>,
 }
 
+// Each part is between 0 (easiest) and 1 (hardest), total is their weighted mean
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct DifficultyScore {
+    pub total: f64,
+    pub word_rarity: f64,
+    pub hint_kanji_rarity: f64,
+    pub irregularness: f64,
+    pub answer_class: f64,
+    pub near_misses: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Candidate {
+    pub ji: Ji,
+    // Indices of the hints the kanji fits
+    pub fits: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SolvabilityReport {
+    // Kanji other than the answer that fit every hint, a fair puzzle has none
+    pub unintended: Vec<Candidate>,
+    // Kanji that fit every hint but one
+    pub decoys: Vec<Candidate>,
+}
+
 #[derive(Debug)]
 pub struct Generator<'a, R> {
     pub rng: R,
@@ -63,7 +137,7 @@ impl<'a, R> Generator<'a, R> {
 }
 
 impl<'g, R: rand::Rng> Generator<'g, R> {
-    pub fn choose_puzzle(&'g mut self, options: &PuzzleOptions) -> Puzzle {
+    pub fn choose_puzzle(&mut self, options: &PuzzleOptions) -> Puzzle {
         let start = Instant::now();
         let mut ks = self.choose_kanji(options);
         if let Some(fixed) = options.fixed {
@@ -99,14 +173,27 @@ impl<'g, R: rand::Rng> Generator<'g, R> {
                 options.min_kanji_class <= class && class <= options.max_kanji_class
             })
             .collect_vec();
-        weighted_shuffle(&ks, &mut self.rng, |k| {
+        let ks = weighted_shuffle(&ks, &mut self.rng, |k| {
             apply_bias(
                 options.rare_kanji_bias,
                 k.rank as f64 / MAX_KANJI_RANK as f64,
             )
         })
         .map(|k| k.ji)
-        .collect()
+        .collect_vec();
+        // Excluded kanji come last even when themed, so a theme never brings back a recent
+        // answer while there are fresh ones
+        let (fresh, excluded): (Vec<_>, Vec<_>) =
+            ks.into_iter().partition(|ji| !options.exclude.contains(ji));
+        let themed_first = |ks: Vec<Ji>| match &options.theme {
+            Some(theme) => {
+                let (themed, others): (Vec<_>, Vec<_>) =
+                    ks.into_iter().partition(|ji| theme.contains(ji));
+                [themed, others].concat()
+            }
+            None => ks,
+        };
+        [themed_first(fresh), themed_first(excluded)].concat()
     }
 
     pub fn find_usable_hints(&self, answer: Ji, options: &PuzzleOptions) -> Vec<Hint> {
@@ -115,6 +202,12 @@ impl<'g, R: rand::Rng> Generator<'g, R> {
             .values()
             .skip_while(|x| x.word.rank <= options.min_word_rarity)
             .take_while(|x| x.word.rank <= options.max_word_rarity)
+            .filter(|x| !options.exclude_words.contains(&x.word.text))
+            .filter(|x| {
+                (options.include_word_tags.is_empty()
+                    || x.tags.iter().any(|t| options.include_word_tags.contains(t)))
+                    && !x.tags.iter().any(|t| options.exclude_word_tags.contains(t))
+            })
             .filter(|two| {
                 let class_a = self.kanji_data.kanji_metas.get(&two.a).unwrap().class;
                 let class_b = self.kanji_data.kanji_metas.get(&two.b).unwrap().class;
@@ -192,24 +285,99 @@ impl<'g, R: rand::Rng> Generator<'g, R> {
                 .map(|s| s[0])
                 .collect();
             let all_hints = [&chosen_hints[..], &extra_hints[..]].concat();
+            if self.contains_same_hint(&all_hints) || self.contains_same_numerical_hint(&all_hints)
+            {
+                continue;
+            }
 
-            if !self.contains_same_hint(&all_hints)
-                && !self.contains_same_numerical_hint(&all_hints)
-                && self
-                    .find_unintended_solutions(answer, &chosen_hints)
-                    .is_empty()
+            // Extra hints are revealed after the rest, so all of them are ordered together and
+            // the ones shown first have to make the answer unique by themselves
+            let all_hints = self.order_hints(&all_hints, options.hint_ordering);
+            let (chosen_hints, extra_hints) = all_hints.split_at(chosen_hints.len());
+            let chosen_hints = chosen_hints.iter().collect_vec();
+            if !self
+                .find_unintended_solutions(answer, &chosen_hints)
+                .is_empty()
             {
+                continue;
+            }
+            // Subsets keep the order of the hints they are taken from
+            if options.minimize_hints {
+                let hints = self
+                    .minimal_hints(answer, &chosen_hints, MIN_MINIMIZED_HINTS)
+                    .unwrap_or(chosen_hints);
                 return Some(Puzzle {
                     answer,
-                    hints: chosen_hints.iter().copied().cloned().collect(),
-                    extra_hints: extra_hints.iter().copied().cloned().collect(),
+                    hints: hints.into_iter().cloned().collect(),
+                    extra_hints: vec![],
                 });
             }
+            return Some(Puzzle {
+                answer,
+                hints: chosen_hints.into_iter().cloned().collect(),
+                extra_hints: extra_hints.to_vec(),
+            });
         }
 
         None
     }
 
+    // Smallest subset of the hints, with at least min of them, that has no unintended solutions
+    pub fn minimal_hints<'h>(
+        &self,
+        answer: Ji,
+        hints: &[&'h Hint],
+        min: usize,
+    ) -> Option<Vec<&'h Hint>> {
+        (min..=hints.len()).find_map(|n| {
+            hints
+                .iter()
+                .copied()
+                .combinations(n)
+                .find(|hs| self.find_unintended_solutions(answer, hs).is_empty())
+        })
+    }
+
+    // How many hints, revealed in order, it takes until only the answer fits them
+    pub fn determined_after(&self, answer: Ji, hints: &[&Hint]) -> Option<usize> {
+        (1..=hints.len()).find(|&n| {
+            self.find_unintended_solutions(answer, &hints[..n])
+                .is_empty()
+        })
+    }
+
+    fn order_hints(&mut self, hints: &[&Hint], ordering: HintOrdering) -> Vec<Hint> {
+        let by_difficulty = |x: &&&Hint| OrderedFloat(self.hint_difficulty(x));
+        let mut hints = match ordering {
+            HintOrdering::AsChosen | HintOrdering::Random => hints.iter().collect_vec(),
+            HintOrdering::HardToEasy => hints
+                .iter()
+                .sorted_by_key(by_difficulty)
+                .rev()
+                .collect_vec(),
+            HintOrdering::EasyToHard => hints.iter().sorted_by_key(by_difficulty).collect_vec(),
+        };
+        if ordering == HintOrdering::Random {
+            hints.shuffle(&mut self.rng);
+        }
+        hints.into_iter().copied().cloned().collect()
+    }
+
+    // Between 0 (easiest) and 1 (hardest), from the same parts as estimate_difficulty
+    pub fn hint_difficulty(&self, hint: &Hint) -> f64 {
+        0.5 * self.word_rarity(hint) + 0.3 * self.hint_kanji_rarity(hint) + 0.2 * hint.irregularness
+    }
+
+    // Square root spreads out the common words, which is where most puzzles pick from
+    fn word_rarity(&self, hint: &Hint) -> f64 {
+        (hint.rank as f64 / MAX_WORD_RANK as f64).min(1.0).sqrt()
+    }
+
+    fn hint_kanji_rarity(&self, hint: &Hint) -> f64 {
+        let rank = self.kanji_data.kanjis.get(&hint.hint).unwrap().rank;
+        (rank as f64 / MAX_KANJI_RANK as f64).min(1.0)
+    }
+
     pub fn contains_same_hint(&self, hints: &[&Hint]) -> bool {
         hints.iter().combinations(2).any(|hs| {
             let variants = &self
@@ -241,23 +409,87 @@ impl<'g, R: rand::Rng> Generator<'g, R> {
             .kanjis
             .keys()
             .copied()
-            .filter(|&k| {
-                k != answer
-                    && hints.iter().all(|x| match x.answer_location {
-                        Loc::L => {
-                            let key = String::from_iter(&[k.0, x.hint.0]);
-                            self.word_data.twos.contains_key(&key)
-                        }
-                        Loc::R => {
-                            let key = String::from_iter(&[x.hint.0, k.0]);
-                            self.word_data.twos.contains_key(&key)
-                        }
-                    })
-            })
+            .filter(|&k| k != answer && hints.iter().all(|x| self.fits_hint(k, x)))
             .collect()
     }
+
+    pub fn solvability_report(&self, answer: Ji, hints: &[&Hint]) -> SolvabilityReport {
+        let (unintended, decoys) = self
+            .kanji_data
+            .kanjis
+            .keys()
+            .copied()
+            .filter(|&k| k != answer)
+            .filter_map(|k| {
+                let fits = (0..hints.len())
+                    .filter(|&i| self.fits_hint(k, hints[i]))
+                    .collect_vec();
+                (fits.len() + 1 >= hints.len()).then_some(Candidate { ji: k, fits })
+            })
+            .partition(|c| c.fits.len() == hints.len());
+        SolvabilityReport { unintended, decoys }
+    }
+
+    fn fits_hint(&self, k: Ji, hint: &Hint) -> bool {
+        let key = match hint.answer_location {
+            Loc::L => String::from_iter(&[k.0, hint.hint.0]),
+            Loc::R => String::from_iter(&[hint.hint.0, k.0]),
+        };
+        self.word_data.twos.contains_key(&key)
+    }
+
+    pub fn estimate_difficulty(&self, puzzle: &Puzzle) -> DifficultyScore {
+        let hints = puzzle.hints.iter().collect_vec();
+        let n = hints.len().max(1) as f64;
+
+        let word_rarity = hints.iter().map(|x| self.word_rarity(x)).sum::<f64>() / n;
+        let hint_kanji_rarity = hints.iter().map(|x| self.hint_kanji_rarity(x)).sum::<f64>() / n;
+
+        let irregularness = hints.iter().map(|x| x.irregularness).sum::<f64>() / n;
+
+        let class = self
+            .kanji_data
+            .kanji_metas
+            .get(&puzzle.answer)
+            .unwrap()
+            .class;
+        let answer_class = match class {
+            KanjiClass::Kyoiku => 0.0,
+            KanjiClass::Joyo => 1.0 / 3.0,
+            KanjiClass::Kentei => 2.0 / 3.0,
+            KanjiClass::All => 1.0,
+        };
+
+        // Kanji that fit every hint but one, the more there are the more each hint is needed
+        let near_misses = (0..hints.len())
+            .map(|i| {
+                let rest = [&hints[..i], &hints[i + 1..]].concat();
+                self.find_unintended_solutions(puzzle.answer, &rest).len() as f64
+            })
+            .sum::<f64>()
+            / n;
+        let near_misses = near_misses / (near_misses + 1.0);
+
+        let total = 0.3 * word_rarity
+            + 0.2 * hint_kanji_rarity
+            + 0.15 * irregularness
+            + 0.2 * answer_class
+            + 0.15 * near_misses;
+
+        DifficultyScore {
+            total,
+            word_rarity,
+            hint_kanji_rarity,
+            irregularness,
+            answer_class,
+            near_misses,
+        }
+    }
 }
 
+// Single hints almost never make the answer unique, and would not be much of a puzzle
+static MIN_MINIMIZED_HINTS: usize = 2;
+
 fn apply_bias(bias: f64, x: f64) -> f64 {
     1.0 + (bias - 1.0) * x
 }
@@ -335,7 +567,222 @@ fn multi_cartesian_diagonal(dims: &[usize]) -> impl Iterator<Item = Vec<usize>>
 
 #[cfg(test)]
 mod tests {
+    use indexmap::IndexMap;
+
     use super::*;
+    use crate::data::{Compound2, Kanji, Word};
+
+    // Kanji ranked in the order given, and the two kanji words made from them
+    fn data(kanjis: &str, words: &[(&str, usize)]) -> (KanjiData, WordData) {
+        let mut kanji_data = KanjiData {
+            kanjis: IndexMap::new(),
+            kanji_metas: IndexMap::new(),
+        };
+        for (i, c) in kanjis.chars().enumerate() {
+            let ji = Ji(c);
+            kanji_data.kanjis.insert(
+                ji,
+                Kanji {
+                    ji,
+                    rank: (i + 1) * 100,
+                    count: 0,
+                },
+            );
+            kanji_data.kanji_metas.insert(
+                ji,
+                KanjiMeta {
+                    level: "10".to_owned(),
+                    class: KanjiClass::Kyoiku,
+                    stroke_count: 1,
+                    radical: String::new(),
+                    on: vec![],
+                    kun: vec![],
+                    variants: vec![],
+                    components: None,
+                    stroke_parts: vec![],
+                },
+            );
+        }
+        let twos = words
+            .iter()
+            .map(|&(text, rank)| {
+                let mut chars = text.chars().map(Ji);
+                let two = Compound2 {
+                    a: chars.next().unwrap(),
+                    b: chars.next().unwrap(),
+                    word: Word {
+                        text: text.to_owned(),
+                        reading: String::new(),
+                        rank,
+                        tags: String::new(),
+                    },
+                    irregularness: 0.0,
+                    tags: vec![],
+                };
+                (text.to_owned(), two)
+            })
+            .collect();
+        (kanji_data, WordData { twos })
+    }
+
+    fn options() -> PuzzleOptions {
+        PuzzleOptions {
+            min_kanji_class: KanjiClass::Kyoiku,
+            max_kanji_class: KanjiClass::All,
+            rare_kanji_bias: 1.0,
+            fixed: None,
+            exclude: HashSet::new(),
+            theme: None,
+            min_word_rarity: 0,
+            max_word_rarity: MAX_WORD_RANK,
+            min_word_kanji_class: KanjiClass::Kyoiku,
+            max_word_kanji_class: KanjiClass::All,
+            exclude_words: HashSet::new(),
+            include_word_tags: HashSet::new(),
+            exclude_word_tags: HashSet::new(),
+            irregular_hint_bias: 1.0,
+            rare_kanji_hint_bias: 1.0,
+            rare_word_hint_bias: 1.0,
+            num_hints: 3,
+            guarantee_answer_by: 3,
+            minimize_hints: false,
+            hint_ordering: HintOrdering::AsChosen,
+        }
+    }
+
+    #[test]
+    fn themed_first_and_excluded_last() {
+        let (kanji_data, word_data) = data("一二三四五六七八", &[]);
+        let mut options = options();
+        options.theme = Some("一二三".chars().map(Ji).collect());
+        options.exclude = "三四".chars().map(Ji).collect();
+        let group = |ji: &Ji| match (
+            options.exclude.contains(ji),
+            options.theme.as_ref().unwrap().contains(ji),
+        ) {
+            (false, true) => 0,
+            (false, false) => 1,
+            (true, true) => 2,
+            (true, false) => 3,
+        };
+        let mut g = Generator::new(rand::thread_rng(), &kanji_data, &word_data);
+        for _ in 0..20 {
+            let ks = g.choose_kanji(&options);
+            assert_eq!(ks.len(), 8);
+            assert!(ks.iter().map(group).is_sorted(), "{ks:?}");
+        }
+    }
+
+    fn hint(answer: char, answer_location: Loc, hint: char, rank: usize) -> Hint {
+        Hint {
+            answer: Ji(answer),
+            answer_location,
+            hint: Ji(hint),
+            rank,
+            irregularness: 0.0,
+        }
+    }
+
+    #[test]
+    fn orders_hints_by_difficulty() {
+        let (kanji_data, word_data) = data("日本中月火水", &[]);
+        let hints = [
+            hint('日', Loc::L, '本', 2_000),
+            hint('日', Loc::R, '中', 300_000),
+            hint('日', Loc::R, '月', 50),
+            hint('日', Loc::L, '火', 20_000),
+            hint('日', Loc::R, '水', 20_000),
+        ];
+        let hints = hints.iter().collect_vec();
+        let mut g = Generator::new(rand::thread_rng(), &kanji_data, &word_data);
+        let mut difficulties = |ordering| {
+            g.order_hints(&hints, ordering)
+                .iter()
+                .map(|h| g.hint_difficulty(h))
+                .collect_vec()
+        };
+
+        let ds = difficulties(HintOrdering::HardToEasy);
+        assert!(ds.windows(2).all(|w| w[0] >= w[1]), "{ds:?}");
+        assert!(ds[0] > ds[4]);
+        let ds = difficulties(HintOrdering::EasyToHard);
+        assert!(ds.windows(2).all(|w| w[0] <= w[1]), "{ds:?}");
+        let as_chosen = g.order_hints(&hints, HintOrdering::AsChosen);
+        assert_eq!(
+            as_chosen.iter().map(|h| h.hint).collect_vec(),
+            hints.iter().map(|h| h.hint).collect_vec()
+        );
+    }
+
+    #[test]
+    fn minimal_hints_are_the_smallest_unique_subset() {
+        let (kanji_data, word_data) = data(
+            "日月火本曜中",
+            &[
+                ("日本", 1),
+                ("月本", 1),
+                ("日曜", 1),
+                ("月曜", 1),
+                ("火曜", 1),
+                ("中日", 1),
+                ("中火", 1),
+            ],
+        );
+        let g = Generator::new(rand::thread_rng(), &kanji_data, &word_data);
+        // Each alone fits another kanji too, 本 and 曜 both fit 月, 曜 and 中 both fit 火
+        let hon = hint('日', Loc::L, '本', 1);
+        let you = hint('日', Loc::L, '曜', 1);
+        let naka = hint('日', Loc::R, '中', 1);
+        let hints = [&hon, &you, &naka];
+        let jis = |hs: Vec<&Hint>| hs.iter().map(|h| h.hint).collect_vec();
+
+        let minimal = g.minimal_hints(Ji('日'), &hints, 1).unwrap();
+        assert_eq!(jis(minimal), [Ji('本'), Ji('中')]);
+        let minimal = g.minimal_hints(Ji('日'), &hints, 3).unwrap();
+        assert_eq!(jis(minimal), [Ji('本'), Ji('曜'), Ji('中')]);
+        assert!(g.minimal_hints(Ji('日'), &[&hon, &you], 1).is_none());
+    }
+
+    #[test]
+    fn scores_rarer_puzzles_higher() {
+        let (mut kanji_data, word_data) =
+            data("日月本曜", &[("日本", 1), ("月本", 1), ("日曜", 1)]);
+        let puzzle = |rank| Puzzle {
+            answer: Ji('日'),
+            hints: vec![
+                hint('日', Loc::L, '本', rank),
+                hint('日', Loc::L, '曜', rank),
+            ],
+            extra_hints: vec![],
+        };
+        let score = |kanji_data: &KanjiData, rank| {
+            Generator::new(rand::thread_rng(), kanji_data, &word_data)
+                .estimate_difficulty(&puzzle(rank))
+        };
+
+        let common = score(&kanji_data, 0);
+        assert_eq!(common.word_rarity, 0.0);
+        assert_eq!(common.answer_class, 0.0);
+        // Without 曜 the answer could also be 月, so on average half a near miss per hint
+        assert!((common.near_misses - 1.0 / 3.0).abs() < 1e-9);
+        let parts = [
+            common.word_rarity,
+            common.hint_kanji_rarity,
+            common.irregularness,
+            common.answer_class,
+            common.near_misses,
+        ];
+        assert!(parts.iter().all(|x| (0.0..=1.0).contains(x)), "{parts:?}");
+
+        let rare = score(&kanji_data, MAX_WORD_RANK);
+        assert_eq!(rare.word_rarity, 1.0);
+        assert!(rare.total > common.total);
+
+        kanji_data.kanji_metas.get_mut(&Ji('日')).unwrap().class = KanjiClass::Kentei;
+        let uncommon_answer = score(&kanji_data, 0);
+        assert!(uncommon_answer.answer_class > common.answer_class);
+        assert!(uncommon_answer.total > common.total);
+    }
 
     #[test]
     fn shuffle_obvious() {
@@ -0,0 +1,112 @@
+// Published daily puzzles must never change, so a sample of them is pinned in a snapshot file.
+// Run with KDLE_UPDATE_GOLDEN=1 to record it again after a change that is meant to alter them.
+
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::cache::PuzzleCache;
+use crate::calendar::Calendar;
+use crate::data::{self, Curation};
+use crate::schedule::Schedule;
+use crate::store::Store;
+use crate::{ApiState, Difficulty, ReqMode, ResPuzzle};
+
+static GOLDEN_DAILIES: &str = "golden/dailies.json";
+
+// Around each cutover that could have changed past puzzles
+static DATES: [&str; 7] = [
+    "2024-10-01",
+    "2026-10-18",
+    "2026-10-19",
+    "2026-10-20",
+    "2026-10-21",
+    "2026-10-24",
+    "2026-11-02",
+];
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct GoldenPuzzle {
+    id: u64,
+    difficulty: Difficulty,
+    answer: String,
+    hints: Vec<String>,
+    extra_hints: Vec<String>,
+}
+
+impl GoldenPuzzle {
+    fn new(puzzle: &ResPuzzle) -> GoldenPuzzle {
+        GoldenPuzzle {
+            id: puzzle.id,
+            difficulty: puzzle.difficulty,
+            answer: puzzle.answer.0.to_string(),
+            hints: puzzle.hints.iter().map(|h| h.to_string()).collect(),
+            extra_hints: puzzle.extra_hints.iter().map(|h| h.to_string()).collect(),
+        }
+    }
+}
+
+fn load_state() -> ApiState {
+    let curation = Curation::load().unwrap();
+    ApiState {
+        data: data::load_data_set(Path::new(data::GENERATED_DIR), &curation).unwrap(),
+        archive: data::load_archive().unwrap(),
+        stroke_paths: IndexMap::new(),
+        cache: PuzzleCache::new(1, Duration::ZERO),
+        store: RwLock::new(Store::default()),
+        schedule: Schedule::default(),
+        calendar: RwLock::new(Calendar::default()),
+        blocked_words: RwLock::new(HashMap::new()),
+        admin_token: None,
+        served: RwLock::new(IndexMap::new()),
+    }
+}
+
+#[tokio::test]
+async fn dailies_are_unchanged() {
+    let state = load_state();
+    let scope = (None, Schedule::default());
+    let mut dailies = BTreeMap::new();
+    for date in DATES {
+        let day: DateTime<Utc> = date
+            .parse::<NaiveDate>()
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc();
+        for mode in ReqMode::ALL {
+            let puzzle = state.daily_res_puzzle(&scope, day, mode).await;
+            dailies.insert(
+                format!("{date} {}", mode.name()),
+                GoldenPuzzle::new(&puzzle),
+            );
+        }
+    }
+
+    if env::var("KDLE_UPDATE_GOLDEN").is_ok() {
+        fs::write(
+            GOLDEN_DAILIES,
+            serde_json::to_string_pretty(&dailies).unwrap() + "\n",
+        )
+        .unwrap();
+        return;
+    }
+    let golden = fs::read_to_string(GOLDEN_DAILIES).unwrap_or_else(|_| {
+        panic!("{GOLDEN_DAILIES} missing, record it with KDLE_UPDATE_GOLDEN=1")
+    });
+    let golden: BTreeMap<String, GoldenPuzzle> = serde_json::from_str(&golden).unwrap();
+    for (key, puzzle) in &dailies {
+        assert_eq!(
+            Some(puzzle),
+            golden.get(key),
+            "daily puzzle of {key} changed"
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use std::cmp::Ordering;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::data::{Ji, KanjiData};
+
+// How a guess of a registered variant of the answer, like 學 for 学, is treated
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VariantPolicy {
+    #[default]
+    Accept,
+    Close,
+    Strict,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Correct,
+    // Counts as correct, but the answer is written in its standard form
+    Variant,
+    // Does not count, the player should try the standard form
+    Close,
+    Wrong,
+}
+
+impl Verdict {
+    pub fn is_correct(&self) -> bool {
+        matches!(self, Verdict::Correct | Verdict::Variant)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Strokes {
+    More,
+    Fewer,
+    Same,
+}
+
+// How a wrong guess relates to the answer, for warmer/colder feedback
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Feedback {
+    pub same_radical: bool,
+    // Whether the answer has more or fewer strokes than the guess
+    pub answer_strokes: Strokes,
+    pub shared_on: Vec<String>,
+    pub same_class: bool,
+    pub shared_components: Vec<Ji>,
+}
+
+// Variants are only listed on kanji that have metadata, so check both directions
+pub fn is_variant(kanji_data: &KanjiData, a: Ji, b: Ji) -> bool {
+    let lists = |x: Ji, y: Ji| {
+        kanji_data
+            .kanji_metas
+            .get(&x)
+            .is_some_and(|m| m.variants.contains(&y))
+    };
+    lists(a, b) || lists(b, a)
+}
+
+pub fn evaluate(kanji_data: &KanjiData, answer: Ji, guess: Ji, policy: VariantPolicy) -> Verdict {
+    if guess == answer {
+        Verdict::Correct
+    } else if is_variant(kanji_data, answer, guess) {
+        match policy {
+            VariantPolicy::Accept => Verdict::Variant,
+            VariantPolicy::Close => Verdict::Close,
+            VariantPolicy::Strict => Verdict::Wrong,
+        }
+    } else {
+        Verdict::Wrong
+    }
+}
+
+pub fn feedback(kanji_data: &KanjiData, answer: Ji, guess: Ji) -> Option<Feedback> {
+    let a = kanji_data.kanji_metas.get(&answer)?;
+    let g = kanji_data.kanji_metas.get(&guess)?;
+    Some(Feedback {
+        same_radical: a.radical == g.radical,
+        answer_strokes: match a.stroke_count.cmp(&g.stroke_count) {
+            Ordering::Greater => Strokes::More,
+            Ordering::Less => Strokes::Fewer,
+            Ordering::Equal => Strokes::Same,
+        },
+        shared_on: a.on.iter().filter(|x| g.on.contains(x)).cloned().collect(),
+        same_class: a.class == g.class,
+        shared_components: match (&a.components, &g.components) {
+            (Some(x), Some(y)) => x
+                .leaves()
+                .into_iter()
+                .unique()
+                .filter(|&c| y.contains(c))
+                .collect(),
+            _ => vec![],
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+
+    use super::*;
+    use crate::data::{Component, KanjiClass, KanjiMeta};
+
+    fn meta(variants: &[char]) -> KanjiMeta {
+        KanjiMeta {
+            level: "10".to_owned(),
+            class: KanjiClass::Kyoiku,
+            stroke_count: 8,
+            radical: "子".to_owned(),
+            on: vec!["ガク".to_owned()],
+            kun: vec![],
+            variants: variants.iter().copied().map(Ji).collect(),
+            components: None,
+            stroke_parts: vec![],
+        }
+    }
+
+    #[test]
+    fn variants_follow_policy() {
+        let kanji_data = KanjiData {
+            kanjis: IndexMap::new(),
+            kanji_metas: [(Ji('学'), meta(&['學']))].into_iter().collect(),
+        };
+        let (answer, guess) = (Ji('学'), Ji('學'));
+        assert_eq!(
+            evaluate(&kanji_data, answer, guess, VariantPolicy::Accept),
+            Verdict::Variant
+        );
+        assert_eq!(
+            evaluate(&kanji_data, answer, guess, VariantPolicy::Close),
+            Verdict::Close
+        );
+        assert_eq!(
+            evaluate(&kanji_data, answer, guess, VariantPolicy::Strict),
+            Verdict::Wrong
+        );
+        // Only 学 has metadata, the variant still counts when it is the answer
+        assert_eq!(
+            evaluate(&kanji_data, guess, answer, VariantPolicy::Accept),
+            Verdict::Variant
+        );
+        assert_eq!(
+            evaluate(&kanji_data, answer, Ji('字'), VariantPolicy::Accept),
+            Verdict::Wrong
+        );
+    }
+
+    #[test]
+    fn feedback_compares_metas() {
+        let mut ji = meta(&[]);
+        ji.stroke_count = 6;
+        ji.on = vec!["ジ".to_owned()];
+        ji.components = Some(Component::Compound(
+            '⿱',
+            vec![Component::Ji(Ji('宀')), Component::Ji(Ji('子'))],
+        ));
+        let mut xue = meta(&[]);
+        xue.components = Some(Component::Compound(
+            '⿱',
+            vec![Component::Ji(Ji('⺍')), Component::Ji(Ji('子'))],
+        ));
+        let mut gaku = meta(&[]);
+        gaku.class = KanjiClass::Kentei;
+        let kanji_data = KanjiData {
+            kanjis: IndexMap::new(),
+            kanji_metas: [(Ji('学'), xue), (Ji('字'), ji), (Ji('斈'), gaku)]
+                .into_iter()
+                .collect(),
+        };
+        assert_eq!(
+            feedback(&kanji_data, Ji('学'), Ji('字')),
+            Some(Feedback {
+                same_radical: true,
+                answer_strokes: Strokes::More,
+                shared_on: vec![],
+                same_class: true,
+                shared_components: vec![Ji('子')],
+            })
+        );
+        assert_eq!(
+            feedback(&kanji_data, Ji('学'), Ji('斈')),
+            Some(Feedback {
+                same_radical: true,
+                answer_strokes: Strokes::Same,
+                shared_on: vec!["ガク".to_owned()],
+                same_class: false,
+                shared_components: vec![],
+            })
+        );
+        assert_eq!(feedback(&kanji_data, Ji('学'), Ji('國')), None);
+    }
+}
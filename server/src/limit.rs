@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use axum::extract::{ConnectInfo, Query, Request, State};
+use axum::http::header::{AUTHORIZATION, RETRY_AFTER};
+use axum::http::{HeaderMap, StatusCode, Uri};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+// Buckets that have filled up again are forgotten once there are this many
+static MAX_BUCKETS: usize = 10_000;
+// Looking for full buckets goes through all of them, so it is not done more often than this
+static PRUNE_INTERVAL: Duration = Duration::from_secs(1);
+
+// Routes that generate a new puzzle on every request, the rest are cheap or cached
+static EXPENSIVE_ROUTES: [&str; 6] = [
+    "/v1/random",
+    "/v2/random",
+    "/v1/review",
+    "/v1/day",
+    "/v1/fixed",
+    "/v1/solvability",
+];
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+#[derive(Debug)]
+struct Buckets {
+    buckets: HashMap<String, Bucket>,
+    pruned: Option<Instant>,
+}
+
+// A token bucket per client, holding up to capacity requests and refilled over per
+#[derive(Debug)]
+pub struct RateLimiter {
+    buckets: Mutex<Buckets>,
+    capacity: f64,
+    refill: f64,
+}
+
+impl RateLimiter {
+    pub fn new(capacity: u64, per: Duration) -> RateLimiter {
+        RateLimiter {
+            buckets: Mutex::new(Buckets {
+                buckets: HashMap::new(),
+                pruned: None,
+            }),
+            capacity: capacity as f64,
+            refill: capacity as f64 / per.as_secs_f64(),
+        }
+    }
+
+    // How long to wait before trying again if the client is out of requests
+    fn check(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap();
+        let Buckets { buckets, pruned } = &mut *buckets;
+        if buckets.len() >= MAX_BUCKETS
+            && pruned.is_none_or(|at| now.saturating_duration_since(at) >= PRUNE_INTERVAL)
+        {
+            buckets.retain(|_, b| self.tokens(b, now) < self.capacity);
+            buckets.shrink_to_fit();
+            *pruned = Some(now);
+        }
+        let bucket = buckets.entry(key.to_owned()).or_insert(Bucket {
+            tokens: self.capacity,
+            updated: now,
+        });
+        bucket.tokens = self.tokens(bucket, now);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.refill))
+        }
+    }
+
+    fn tokens(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        (bucket.tokens + elapsed * self.refill).min(self.capacity)
+    }
+}
+
+// A range of addresses such as 172.16.0.0/12, or a single address
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Network {
+    addr: IpAddr,
+    prefix: u32,
+}
+
+impl FromStr for Network {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Network> {
+        let (addr, prefix) = s.split_once('/').unwrap_or((s, ""));
+        let addr: IpAddr = addr
+            .parse()
+            .with_context(|| format!("Not an address {s:?}"))?;
+        let bits = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            "" => bits,
+            prefix => prefix
+                .parse()
+                .ok()
+                .filter(|&p| p <= bits)
+                .with_context(|| format!("Not a prefix length {s:?}"))?,
+        };
+        Ok(Network { addr, prefix })
+    }
+}
+
+impl Network {
+    fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(a), IpAddr::V4(b)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix).unwrap_or(0);
+                u32::from(a) & mask == u32::from(b) & mask
+            }
+            (IpAddr::V6(a), IpAddr::V6(b)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix).unwrap_or(0);
+                u128::from(a) & mask == u128::from(b) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Limits {
+    pub cheap: RateLimiter,
+    pub expensive: RateLimiter,
+    // Only requests from these may say which client they are for
+    pub trusted_proxies: Vec<Network>,
+    // Callers with this bearer token, like the Discord bot, are limited by the user they
+    // give rather than by their one address
+    pub bot_token: Option<String>,
+}
+
+// Behind the proxy every request comes from it, so the client is the address it forwards.
+// Anyone else could send any address, so theirs is not believed.
+fn client_ip(headers: &HeaderMap, peer: SocketAddr, trusted_proxies: &[Network]) -> String {
+    let peer = peer.ip();
+    trusted_proxies
+        .iter()
+        .any(|n| n.contains(peer))
+        .then(|| headers.get("x-forwarded-for"))
+        .flatten()
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| x.rsplit(',').next())
+        .map(|x| x.trim().to_owned())
+        .filter(|x| !x.is_empty())
+        .unwrap_or_else(|| peer.to_canonical().to_string())
+}
+
+// Users are only believed from those that speak for many of them, the bot by its token or a
+// service on the trusted network calling directly instead of through the proxy
+fn client_key(headers: &HeaderMap, uri: &Uri, peer: SocketAddr, limits: &Limits) -> String {
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| x.strip_prefix("Bearer "));
+    let trusted = limits.trusted_proxies.iter().any(|n| n.contains(peer.ip()))
+        && !headers.contains_key("x-forwarded-for");
+    let user = Query::<HashMap<String, String>>::try_from_uri(uri)
+        .ok()
+        .and_then(|Query(mut query)| query.remove("user"))
+        .filter(|_| trusted || token.is_some_and(|t| limits.bot_token.as_deref() == Some(t)));
+    match user {
+        Some(user) => format!("user:{user}"),
+        None => format!("ip:{}", client_ip(headers, peer, &limits.trusted_proxies)),
+    }
+}
+
+// Limits every client by address, or by user for the callers that may give one
+pub async fn limit(
+    State(limits): State<Arc<Limits>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let limiter = if EXPENSIVE_ROUTES.contains(&request.uri().path()) {
+        &limits.expensive
+    } else {
+        &limits.cheap
+    };
+    let key = client_key(request.headers(), request.uri(), peer, &limits);
+
+    if let Err(wait) = limiter.check(&key, Instant::now()) {
+        tracing::debug!("Rate limited {key} for {wait:?}");
+        let secs = wait.as_secs_f64().ceil().max(1.0) as u64;
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(RETRY_AFTER, secs.to_string())],
+        )
+            .into_response();
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::routing::get;
+    use axum::{middleware, Router};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[test]
+    fn buckets_refill_per_client() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(4));
+        let now = Instant::now();
+        assert!(limiter.check("a", now).is_ok());
+        assert!(limiter.check("a", now).is_ok());
+        assert_eq!(limiter.check("a", now), Err(Duration::from_secs(2)));
+        assert!(limiter.check("b", now).is_ok());
+        assert!(limiter.check("a", now + Duration::from_secs(2)).is_ok());
+        assert!(limiter.check("a", now + Duration::from_secs(2)).is_err());
+    }
+
+    #[test]
+    fn forwarded_address_only_from_proxy() {
+        let proxies = ["172.16.0.0/12".parse().unwrap()];
+        let peer = SocketAddr::from(([172, 18, 0, 5], 41234));
+        let mut headers = HeaderMap::new();
+        assert_eq!(client_ip(&headers, peer, &proxies), "172.18.0.5");
+        headers.insert("x-forwarded-for", "10.0.0.1, 203.0.113.7".parse().unwrap());
+        assert_eq!(client_ip(&headers, peer, &proxies), "203.0.113.7");
+        let peer = SocketAddr::from(([198, 51, 100, 2], 41234));
+        assert_eq!(client_ip(&headers, peer, &proxies), "198.51.100.2");
+        assert!("10.0.0.1/33".parse::<Network>().is_err());
+    }
+
+    #[test]
+    fn users_only_from_the_bot_or_the_trusted_network() {
+        let limits = Limits {
+            cheap: RateLimiter::new(1, Duration::from_secs(1)),
+            expensive: RateLimiter::new(1, Duration::from_secs(1)),
+            trusted_proxies: vec!["172.16.0.0/12".parse().unwrap()],
+            bot_token: Some("secret".to_owned()),
+        };
+        let uri = "/v1/today?mode=classic&user=42".parse().unwrap();
+        let outside = SocketAddr::from(([198, 51, 100, 2], 41234));
+        let inside = SocketAddr::from(([172, 18, 0, 5], 41234));
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            client_key(&headers, &uri, outside, &limits),
+            "ip:198.51.100.2"
+        );
+        assert_eq!(client_key(&headers, &uri, inside, &limits), "user:42");
+
+        headers.insert("x-forwarded-for", "203.0.113.7".parse().unwrap());
+        assert_eq!(
+            client_key(&headers, &uri, inside, &limits),
+            "ip:203.0.113.7"
+        );
+        headers.insert(AUTHORIZATION, "Bearer wrong".parse().unwrap());
+        assert_eq!(
+            client_key(&headers, &uri, inside, &limits),
+            "ip:203.0.113.7"
+        );
+        headers.insert(AUTHORIZATION, "Bearer secret".parse().unwrap());
+        assert_eq!(client_key(&headers, &uri, inside, &limits), "user:42");
+        assert_eq!(client_key(&headers, &uri, outside, &limits), "user:42");
+        let uri = "/v1/today?mode=classic".parse().unwrap();
+        assert_eq!(
+            client_key(&headers, &uri, outside, &limits),
+            "ip:198.51.100.2"
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_with_retry_after() {
+        let limits = Arc::new(Limits {
+            cheap: RateLimiter::new(1, Duration::from_secs(30)),
+            expensive: RateLimiter::new(1, Duration::from_secs(30)),
+            trusted_proxies: vec![],
+            bot_token: None,
+        });
+        let app = Router::new()
+            .route("/v1/today", get(|| async { "ok" }))
+            .layer(middleware::from_fn_with_state(limits, limit));
+        let request = || {
+            let mut request = Request::get("/v1/today").body(Body::empty()).unwrap();
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from(([203, 0, 113, 7], 41234))));
+            request
+        };
+
+        let response = app.clone().oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[RETRY_AFTER], "30");
+    }
+}
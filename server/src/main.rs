@@ -1,51 +1,400 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Instant;
 use std::{env, time::Duration};
 
 use anyhow::Result;
-use axum::extract::{self, State};
+use axum::extract::{self, Path, State};
 use axum::Json;
 use axum::{
-    error_handling::HandleErrorLayer,
-    http::{Method, StatusCode},
-    routing::get,
-    BoxError, Router,
+    http::{header::AUTHORIZATION, HeaderMap, Method, StatusCode},
+    middleware,
+    routing::{get, post},
+    Router,
 };
-use chrono::{DateTime, Datelike, DurationRound, TimeDelta, Utc, Weekday};
-use data::{Ji, KanjiClass, KanjiData, KanjiMeta, Loc, WordData, MAX_WORD_RANK};
-use generate::{Generator, Hint, Puzzle, PuzzleOptions};
+use cache::{PuzzleCache, PuzzleKey};
+use calendar::Calendar;
+use calibrate::ResultOf;
+#[cfg(feature = "debug-routes")]
+use chrono::DurationRound;
+use chrono::{DateTime, Datelike, NaiveDate, TimeDelta, Utc, Weekday};
+use chrono_tz::Tz;
+use data::{
+    Curation, CurationReason, DataManifest, DataSet, Ji, KanjiClass, KanjiMeta, Loc, StrokePaths,
+    Themes, WordTag, MAX_WORD_RANK,
+};
+use generate::{
+    Candidate, DifficultyScore, Generator, Hint, HintOrdering, MetaHintKind, Puzzle, PuzzleOptions,
+};
+use guess::{Feedback, VariantPolicy, Verdict};
+use indexmap::IndexMap;
+use limit::{Limits, RateLimiter};
 use rand::SeedableRng;
+use schedule::{DaySpec, Schedule};
 use serde::{Deserialize, Serialize};
+use store::Store;
 use tokio::sync::RwLock;
-use tower::{buffer::BufferLayer, limit::RateLimitLayer, ServiceBuilder};
 use tower_http::cors::{Any, CorsLayer};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
+pub mod cache;
+pub mod calendar;
+pub mod calibrate;
 pub mod data;
 pub mod generate;
+#[cfg(test)]
+mod golden;
+pub mod guess;
+pub mod limit;
+pub mod review;
+pub mod schedule;
+pub mod seed;
+pub mod store;
 
 struct ApiState {
-    pub kanji_data: KanjiData,
-    pub word_data: WordData,
-    pub cache: RwLock<BTreeMap<u64, ResPuzzle>>,
+    pub data: DataSet,
+    // Earlier data builds, oldest first, for the daily puzzles of the days they were current
+    pub archive: Vec<DataSet>,
+    pub stroke_paths: StrokePaths,
+    pub cache: PuzzleCache<PuzzleKey, ResPuzzle>,
+    pub store: RwLock<Store>,
+    pub schedule: Schedule,
+    pub calendar: RwLock<Calendar>,
+    // Words of the current build that were blocked after it was made, by the first day they
+    // are blocked on
+    pub blocked_words: RwLock<HashMap<String, NaiveDate>>,
+    pub admin_token: Option<String>,
+    // Recently served puzzles by id, oldest first
+    pub served: RwLock<IndexMap<u64, ServedPuzzle>>,
+}
+
+// What is kept of a served puzzle for clients that were not given its answer
+#[derive(Debug, Clone)]
+struct ServedPuzzle {
+    answer: Ji,
+    decoys: Vec<Candidate>,
+    // Furthest meta hint each player has been given, so that asking again is not counted
+    revealed: HashMap<String, MetaHintKind>,
 }
 
 impl ApiState {
-    fn to_generator<R: rand::Rng>(&self, rng: R) -> Generator<R> {
-        Generator::new(rng, &self.kanji_data, &self.word_data)
+    fn to_generator<R: rand::Rng>(&self, rng: R) -> Generator<'_, R> {
+        Generator::new(rng, &self.data.kanji_data, &self.data.word_data)
     }
 
-    fn to_generator_random(&self) -> Generator<impl rand::Rng> {
+    fn to_generator_random(&self) -> Generator<'_, impl rand::Rng> {
         self.to_generator(rand::thread_rng())
     }
 
-    fn to_generator_seeded(&self, seed: u64) -> Generator<impl rand::Rng> {
-        self.to_generator(rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(seed))
+    // Daily puzzles are made from the data build that was current on their day
+    fn data_for(&self, day: DateTime<Utc>) -> &DataSet {
+        data::current_on(&self.data, &self.archive, day.date_naive())
+    }
+
+    fn daily_seed(&self, day: DateTime<Utc>, mode: ReqMode, difficulty: Difficulty) -> u64 {
+        get_seed(day, mode, difficulty, &self.data_for(day).manifest.version)
+    }
+
+    // The schedule a guild follows, and the key its daily answers are kept under
+    async fn daily_schedule(&self, guild: Option<&str>) -> (Option<String>, Schedule) {
+        let store = self.store.read().await;
+        match guild.and_then(|g| Some((g, store.guild(g)?.schedule.clone()?))) {
+            Some((g, schedule)) => (Some(g.to_owned()), schedule),
+            None => (None, self.schedule.clone()),
+        }
+    }
+
+    async fn daily_puzzle(
+        &self,
+        scope: &(Option<String>, Schedule),
+        day: DateTime<Utc>,
+        mode: ReqMode,
+    ) -> Puzzle {
+        let (key, _) = scope;
+        let window = TimeDelta::days(DAILY_NO_REPEAT_DAYS);
+        // Days since the cutover avoid the answers of the window before them. Those are kept in
+        // the store once made, and ones that are missing are made first, oldest to newest, from
+        // what is known of their own windows.
+        let no_repeat_since = DateTime::from_timestamp_millis(DAILY_NO_REPEAT_SINCE).unwrap();
+        let first = match day >= no_repeat_since {
+            true => day - window,
+            false => day,
+        };
+        let mut answers = self
+            .store
+            .read()
+            .await
+            .daily_answers(key.as_deref(), mode.name())
+            .map(|a| {
+                a.range((first - window).date_naive()..day.date_naive())
+                    .map(|(&d, &ji)| (d, ji))
+                    .collect::<BTreeMap<_, _>>()
+            })
+            .unwrap_or_default();
+        let blocked = self.blocked_words.read().await;
+        let calendar = self.calendar.read().await;
+        // Locked days of the calendar are served as they were approved
+        let puzzle_of = |answers: &BTreeMap<_, _>, day: DateTime<Utc>| match calendar
+            .locked(day.date_naive(), mode.name())
+            .filter(|_| key.is_none())
+        {
+            Some(entry) => entry.puzzle.clone(),
+            None => self.daily_puzzle_from(answers, &blocked, scope, day, mode),
+        };
+        let mut made = vec![];
+        let mut past = first;
+        while past < day {
+            if !answers.contains_key(&past.date_naive()) {
+                let answer = puzzle_of(&answers, past).answer;
+                answers.insert(past.date_naive(), answer);
+                made.push((past.date_naive(), answer));
+            }
+            past += TimeDelta::days(1);
+        }
+        let puzzle = puzzle_of(&answers, day);
+        made.push((day.date_naive(), puzzle.answer));
+        drop((blocked, calendar));
+        // Anyone making the same days at the same time agrees, since they are seeded
+        let mut store = self.store.write().await;
+        store
+            .daily_answers_mut(key.as_deref(), mode.name())
+            .extend(made);
+        store.mark_dirty();
+        puzzle
+    }
+
+    // A daily puzzle generated afresh for the day, under the same rules but not its seed
+    async fn reroll_daily(&self, day: DateTime<Utc>, mode: ReqMode, fixed: Option<Ji>) -> Puzzle {
+        let scope = (None, self.schedule.clone());
+        self.daily_puzzle(&scope, day, mode).await;
+        let store = self.store.read().await;
+        let answers = store
+            .daily_answers(None, mode.name())
+            .cloned()
+            .unwrap_or_default();
+        drop(store);
+        let blocked = self.blocked_words.read().await;
+        let (_, mut options) = self.daily_options(&answers, &blocked, &scope, day, mode);
+        options.fixed = fixed;
+        let data = self.data_for(day);
+        Generator::new(rand::thread_rng(), &data.kanji_data, &data.word_data)
+            .choose_puzzle(&options)
+    }
+
+    fn daily_options(
+        &self,
+        answers: &BTreeMap<NaiveDate, Ji>,
+        blocked: &HashMap<String, NaiveDate>,
+        (_, schedule): &(Option<String>, Schedule),
+        day: DateTime<Utc>,
+        mode: ReqMode,
+    ) -> (Difficulty, PuzzleOptions) {
+        let DaySpec { difficulty, theme } = schedule.day(day);
+        let mut options = ReqPuzzleOptions {
+            mode,
+            difficulty,
+            theme: None,
+        }
+        .to_puzzle_options();
+        options.exclude_words = blocked
+            .iter()
+            .filter(|(_, since)| **since <= day.date_naive())
+            .map(|(text, _)| text.clone())
+            .collect();
+        if day.timestamp_millis() < DAILY_HINT_ORDER_SINCE {
+            options.hint_ordering = HintOrdering::AsChosen;
+        }
+        let data = self.data_for(day);
+        options.theme = theme
+            .and_then(|t| data.themes.get(&t))
+            .map(|ks| ks.iter().copied().collect());
+        if day.timestamp_millis() >= DAILY_NO_REPEAT_SINCE {
+            options.exclude = (1..=DAILY_NO_REPEAT_DAYS)
+                .filter_map(|i| answers.get(&(day - TimeDelta::days(i)).date_naive()))
+                .copied()
+                .collect();
+        }
+        (difficulty, options)
+    }
+
+    fn daily_puzzle_from(
+        &self,
+        answers: &BTreeMap<NaiveDate, Ji>,
+        blocked: &HashMap<String, NaiveDate>,
+        scope: &(Option<String>, Schedule),
+        day: DateTime<Utc>,
+        mode: ReqMode,
+    ) -> Puzzle {
+        let (difficulty, options) = self.daily_options(answers, blocked, scope, day, mode);
+        let data = self.data_for(day);
+        let mut g = Generator::new(
+            rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(self.daily_seed(day, mode, difficulty)),
+            &data.kanji_data,
+            &data.word_data,
+        );
+        if day.timestamp_millis() < DAILY_SCORED_SINCE {
+            return g.choose_puzzle(&options);
+        }
+
+        // Keep generating until a puzzle lands close enough to the target of the day, otherwise
+        // settle for the closest one
+        let target = difficulty.target_score();
+        let mut best: Option<(f64, Puzzle)> = None;
+        for _ in 0..DAILY_SCORE_ATTEMPTS {
+            let puzzle = g.choose_puzzle(&options);
+            let off = (g.estimate_difficulty(&puzzle).total - target).abs();
+            if off <= DAILY_SCORE_TOLERANCE {
+                return puzzle;
+            }
+            if !best.as_ref().is_some_and(|(x, _)| *x <= off) {
+                best = Some((off, puzzle));
+            }
+        }
+        let (off, puzzle) = best.unwrap();
+        tracing::debug!("No daily puzzle close to target, using one off by {off:.3}");
+        puzzle
+    }
+
+    // The daily puzzle as served, identified by its seed, or for a guild by that and its scope
+    async fn daily_res_puzzle(
+        &self,
+        scope: &(Option<String>, Schedule),
+        day: DateTime<Utc>,
+        mode: ReqMode,
+    ) -> ResPuzzle {
+        let DaySpec { difficulty, theme } = scope.1.day(day);
+        let seed = self.daily_seed(day, mode, difficulty);
+        let id = match &scope.0 {
+            Some(guild) => seed::guild_puzzle_id(seed, guild, theme.as_deref()),
+            None => seed,
+        };
+        let puzzle = self.daily_puzzle(scope, day, mode).await;
+        let difficulty = match self
+            .calendar
+            .read()
+            .await
+            .locked(day.date_naive(), mode.name())
+        {
+            Some(entry) if scope.0.is_none() => entry.difficulty,
+            _ => difficulty,
+        };
+        let data = self.data_for(day);
+        let mut puzzle = ResPuzzle::new_from_puzzle(
+            id,
+            &puzzle,
+            &Generator::new(rand::thread_rng(), &data.kanji_data, &data.word_data),
+            difficulty,
+        );
+        puzzle.theme = theme;
+        puzzle.date = Some(day.date_naive());
+        self.remember_served(&puzzle).await;
+        puzzle
+    }
+
+    async fn cached_daily_res_puzzle(
+        &self,
+        scope: &(Option<String>, Schedule),
+        day: DateTime<Utc>,
+        mode: ReqMode,
+    ) -> ResPuzzle {
+        let DaySpec { difficulty, theme } = scope.1.day(day);
+        let mut hasher = DefaultHasher::new();
+        (&scope.0, theme).hash(&mut hasher);
+        let key = PuzzleKey {
+            date: day.date_naive(),
+            mode: mode.name(),
+            difficulty: difficulty.name(),
+            data_version: self.data_for(day).manifest.version.clone(),
+            options_hash: hasher.finish(),
+        };
+        self.cache
+            .get_or_insert_with(key, || self.daily_res_puzzle(scope, day, mode))
+            .await
+    }
+
+    // The day it is for a player, by the timezone they asked for or else that of their guild
+    async fn local_today(&self, guild: Option<&str>, tz: Option<Tz>) -> DateTime<Utc> {
+        let tz = match (tz, guild) {
+            (Some(tz), _) => Some(tz),
+            (None, Some(guild)) => self
+                .store
+                .read()
+                .await
+                .guild(guild)
+                .and_then(|g| g.timezone),
+            (None, None) => None,
+        };
+        get_local_day(Utc::now(), tz.unwrap_or(Tz::UTC))
+    }
+
+    async fn to_res_puzzle(&self, id: u64, puzzle: &Puzzle, difficulty: Difficulty) -> ResPuzzle {
+        let puzzle =
+            ResPuzzle::new_from_puzzle(id, puzzle, &self.to_generator_random(), difficulty);
+        self.remember_served(&puzzle).await;
+        puzzle
+    }
+
+    async fn remember_served(&self, puzzle: &ResPuzzle) {
+        let mut served = self.served.write().await;
+        let entry = served
+            .shift_remove(&puzzle.id)
+            .unwrap_or_else(|| ServedPuzzle {
+                answer: puzzle.answer,
+                decoys: puzzle.decoys.clone(),
+                revealed: HashMap::new(),
+            });
+        served.insert(puzzle.id, entry);
+        if served.len() > MAX_SERVED_LEN {
+            served.shift_remove_index(0);
+        }
+    }
+
+    // Presets tuned by how often players actually solve them, narrowed to the requested theme
+    async fn calibrated_options(
+        &self,
+        req: &ReqPuzzleOptions,
+    ) -> Result<PuzzleOptions, StatusCode> {
+        let mut options = req.to_puzzle_options();
+        if let Some(theme) = &req.theme {
+            let ks = self.data.themes.get(theme).ok_or(StatusCode::BAD_REQUEST)?;
+            options.theme = Some(ks.iter().copied().collect());
+        }
+        options.exclude_words = self.blocked_words.read().await.keys().cloned().collect();
+        let store = self.store.read().await;
+        if let Some(tally) = store.stats.by_difficulty.get(&req.difficulty) {
+            let factor = tally.adjustment(req.difficulty.target_solve_rate());
+            options.rare_kanji_bias *= factor;
+            options.max_word_rarity =
+                ((options.max_word_rarity as f64 * factor) as usize).min(MAX_WORD_RANK);
+        }
+        Ok(options)
+    }
+
+    // Served on every puzzle request, so saved with the next flush rather than right away
+    async fn remember_answer(&self, player: &ReqPlayer, answer: Ji) {
+        let Some(user) = &player.user else {
+            return;
+        };
+        let mut store = self.store.write().await;
+        store.player_mut(user).push_answer(answer);
+        store.mark_dirty();
     }
 }
 
+// Daily puzzles do not reuse an answer from this many previous days
+const DAILY_NO_REPEAT_DAYS: i64 = 60;
+// 2026-10-19, earlier daily puzzles were published before repeats were avoided
+const DAILY_NO_REPEAT_SINCE: i64 = 1_792_368_000_000;
+// Daily puzzles further than this from the target score of their difficulty are regenerated
+const DAILY_SCORE_TOLERANCE: f64 = 0.1;
+const DAILY_SCORE_ATTEMPTS: usize = 8;
+// 2026-10-20, earlier daily puzzles were published before their difficulty was scored
+const DAILY_SCORED_SINCE: i64 = 1_792_454_400_000;
+// 2026-10-23, earlier daily puzzles were published with their hints in the order they were picked
+const DAILY_HINT_ORDER_SINCE: i64 = 1_792_713_600_000;
+
 #[tokio::main]
 #[allow(clippy::needless_return)]
 async fn main() -> Result<()> {
@@ -54,6 +403,11 @@ async fn main() -> Result<()> {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
+    let args = env::args().collect::<Vec<_>>();
+    if args.get(1).is_some_and(|x| x == "data") {
+        return data::run(&args[2..]);
+    }
+
     let port = env::var("KDLE_PORT")
         .ok()
         .and_then(|x| str::parse(&x).ok())
@@ -69,59 +423,173 @@ async fn main() -> Result<()> {
         .and_then(|x| str::parse(&x).ok())
         .unwrap_or(6);
 
-    tracing::info!("Starting to load kanji...");
-    let start = Instant::now();
-    let kanji_data = data::load_kanjis()?;
-    let duration = start.elapsed();
-    tracing::info!("Loaded kanjis in {duration:?}");
+    let cheap_rate_num = std::env::var("KDLE_CHEAP_RATE_NUM")
+        .ok()
+        .and_then(|x| str::parse(&x).ok())
+        .unwrap_or(30);
 
-    tracing::info!("Starting to load words...");
+    let cheap_rate_per = std::env::var("KDLE_CHEAP_RATE_PER")
+        .ok()
+        .and_then(|x| str::parse(&x).ok())
+        .unwrap_or(10);
+
+    let trusted_proxies = env::var("KDLE_TRUSTED_PROXIES")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(str::parse)
+        .collect::<Result<Vec<_>>>()?;
+
+    let store_path = env::var("KDLE_STORE_PATH").unwrap_or_else(|_| "store.json".to_owned());
+    let schedule_path =
+        env::var("KDLE_SCHEDULE_PATH").unwrap_or_else(|_| "schedule.json".to_owned());
+    let calendar_path =
+        env::var("KDLE_CALENDAR_PATH").unwrap_or_else(|_| "calendar.json".to_owned());
+
+    let admin_token = env::var("KDLE_ADMIN_TOKEN").ok().filter(|x| !x.is_empty());
+    let bot_token = env::var("KDLE_BOT_TOKEN").ok().filter(|x| !x.is_empty());
+
+    tracing::info!("Starting to load data...");
     let start = Instant::now();
-    let word_data = data::load_words(&kanji_data)?;
+    let curation = Curation::load()?;
+    let data = data::load_data_set(std::path::Path::new(data::GENERATED_DIR), &curation)?;
+    let archive = data::load_archive()?;
     let duration = start.elapsed();
-    tracing::info!("Loaded words in {duration:?}");
+    tracing::info!(
+        "Loaded data version {} in {duration:?}",
+        data.manifest.version
+    );
+
+    let missing = data.missing_parts();
+    if !missing.is_empty() {
+        tracing::warn!(
+            "Data version {} has no {}, build a new version to add them",
+            data.manifest.version,
+            missing.join(", ")
+        );
+    }
+    let stroke_paths = data::load_stroke_paths(&data.kanji_data)?;
+    let blocked_words = data
+        .word_data
+        .twos
+        .keys()
+        .filter_map(|text| Some((text.clone(), curation.blocked_since(text)?)))
+        .collect();
+
+    let mut store = Store::load(store_path)?;
+    let schedule = Schedule::load(schedule_path)?;
+    let today = schedule::latest_today();
+    schedule.check_update(&store.schedule, today)?;
+    if schedule != store.schedule {
+        store.schedule = schedule.clone();
+        store.save()?;
+    }
+    // Answers of days no one has played yet may have been made from data, lists or a calendar
+    // that changed since
+    store.forget_daily_answers_from(today.succ_opt().unwrap());
+    let calendar = Calendar::load(calendar_path)?;
+
+    let state = Arc::new(ApiState {
+        data,
+        archive,
+        stroke_paths,
+        cache: PuzzleCache::new(MAX_CACHE_LEN, CACHE_TTL),
+        store: RwLock::new(store),
+        schedule,
+        calendar: RwLock::new(calendar),
+        served: RwLock::new(IndexMap::new()),
+        blocked_words: RwLock::new(blocked_words),
+        admin_token,
+    });
+
+    if args.get(1).is_some_and(|x| x == "schedule") {
+        return calendar::run(&state, &args[2..]).await;
+    }
+
+    let flushed = state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(STORE_FLUSH_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = flushed.store.write().await.flush() {
+                tracing::error!("Could not save store: {e:?}");
+            }
+        }
+    });
 
     #[cfg(feature = "debug-routes")]
     let app = Router::new()
         .route("/v1/day", get(get_day))
         .route("/v1/today", get(get_today))
         .route("/v1/fixed", get(get_fixed))
-        .route("/v1/random", get(get_random));
+        .route("/v1/solvability", get(get_solvability))
+        .route("/v1/random", get(get_random))
+        .route("/v1/review", get(get_review))
+        .route("/v1/result", post(post_result))
+        .route("/v1/guess", get(get_guess))
+        .route("/v1/guild", post(post_guild))
+        .route("/v2/today", get(get_today_v2))
+        .route("/v2/random", get(get_random_v2))
+        .route("/v2/puzzle/:id/guess", get(get_puzzle_guess))
+        .route("/v2/puzzle/:id/answer", get(get_puzzle_answer))
+        .route("/v2/puzzle/:id/hint", get(get_meta_hint))
+        .route("/v1/kanji/:ji", get(get_kanji))
+        .route("/v1/word/:text", get(get_word))
+        .route("/v1/themes", get(get_themes))
+        .route("/v1/data", get(get_data_versions))
+        .route("/v1/admin/blocklist", post(post_blocklist))
+        .route("/v1/admin/calendar/reload", post(post_calendar_reload))
+        .route("/v1/calibration", get(get_calibration));
 
     #[cfg(not(feature = "debug-routes"))]
     let app = Router::new()
         .route("/v1/today", get(get_today))
-        .route("/v1/random", get(get_random));
+        .route("/v1/random", get(get_random))
+        .route("/v1/review", get(get_review))
+        .route("/v1/result", post(post_result))
+        .route("/v1/guess", get(get_guess))
+        .route("/v1/guild", post(post_guild))
+        .route("/v2/today", get(get_today_v2))
+        .route("/v2/random", get(get_random_v2))
+        .route("/v2/puzzle/:id/guess", get(get_puzzle_guess))
+        .route("/v2/puzzle/:id/answer", get(get_puzzle_answer))
+        .route("/v2/puzzle/:id/hint", get(get_meta_hint))
+        .route("/v1/kanji/:ji", get(get_kanji))
+        .route("/v1/word/:text", get(get_word))
+        .route("/v1/themes", get(get_themes))
+        .route("/v1/data", get(get_data_versions))
+        .route("/v1/admin/blocklist", post(post_blocklist))
+        .route("/v1/admin/calendar/reload", post(post_calendar_reload))
+        .route("/v1/calibration", get(get_calibration));
+
+    let limits = Arc::new(Limits {
+        cheap: RateLimiter::new(cheap_rate_num, Duration::from_secs(cheap_rate_per)),
+        expensive: RateLimiter::new(rate_num, Duration::from_secs(rate_per)),
+        trusted_proxies,
+        bot_token,
+    });
 
+    // Limited inside the CORS layer so that rejections still carry its headers
     let app = app
-        .with_state(Arc::new(ApiState {
-            word_data,
-            kanji_data,
-            cache: RwLock::new(BTreeMap::new()),
-        }))
+        .with_state(state)
+        .layer(middleware::from_fn_with_state(limits, limit::limit))
         .layer(
             CorsLayer::new()
-                .allow_methods(vec![Method::GET, Method::OPTIONS])
+                .allow_methods(vec![Method::GET, Method::POST, Method::OPTIONS])
                 .allow_origin(Any)
                 .allow_headers(Any)
                 .allow_credentials(false),
-        )
-        .layer(
-            ServiceBuilder::new()
-                .layer(HandleErrorLayer::new(|err: BoxError| async move {
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        format!("Unhandled error: {}", err),
-                    )
-                }))
-                .layer(BufferLayer::new(1024))
-                .layer(RateLimitLayer::new(rate_num, Duration::from_secs(rate_per))),
         );
 
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
     tracing::info!("Listening on {addr}");
     let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
@@ -130,11 +598,76 @@ async fn main() -> Result<()> {
 struct ReqPuzzleOptions {
     difficulty: Difficulty,
     mode: ReqMode,
+    theme: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct ReqTodayPuzzleOptions {
     mode: ReqMode,
+    guild: Option<String>,
+    tz: Option<Tz>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReqPlayer {
+    user: Option<String>,
+    tz: Option<Tz>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReqResult {
+    user: String,
+    answer: Ji,
+    solved: bool,
+    attempts: usize,
+    difficulty: Option<Difficulty>,
+    mode: Option<ReqMode>,
+    // Of the puzzle as it was served, only counted while it is still known
+    id: Option<u64>,
+    // Date of the daily puzzle played, as it was served
+    date: Option<NaiveDate>,
+    tz: Option<Tz>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReqGuess {
+    answer: Ji,
+    guess: Ji,
+    guild: Option<String>,
+    mode: Option<ReqMode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReqPuzzleGuess {
+    guess: Ji,
+    guild: Option<String>,
+    mode: Option<ReqMode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReqMetaHint {
+    kind: MetaHintKind,
+    user: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReqBlock {
+    text: String,
+    reason: CurationReason,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReqLookup {
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReqGuild {
+    guild: String,
+    variant_policy: Option<VariantPolicy>,
+    // Replaces the schedule of the guild, an empty one goes back to the shared schedule
+    schedule: Option<Schedule>,
+    timezone: Option<Tz>,
 }
 
 #[cfg(feature = "debug-routes")]
@@ -142,6 +675,7 @@ struct ReqTodayPuzzleOptions {
 struct ReqDayPuzzleOptions {
     mode: ReqMode,
     date: i64,
+    guild: Option<String>,
 }
 
 #[cfg(feature = "debug-routes")]
@@ -152,9 +686,9 @@ struct ReqPuzzleWithAnswerPuzzleOptions {
     answer: Ji,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
-enum Difficulty {
+pub enum Difficulty {
     Simple,
     Easy,
     Normal,
@@ -163,18 +697,82 @@ enum Difficulty {
     Lunatic2,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
 #[serde(rename_all = "snake_case")]
 enum ReqMode {
     Classic,
     Hidden,
+    // Hidden, with feedback on how close each wrong guess is
+    WarmerColder,
+    // Only the fewest hints that still make the answer unique
+    Golf,
+}
+
+impl Difficulty {
+    // Same as the serialized name
+    fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Simple => "simple",
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+            Difficulty::Lunatic => "lunatic",
+            Difficulty::Lunatic2 => "lunatic2",
+        }
+    }
+
+    // Median estimated difficulty score of puzzles generated with this difficulty's options,
+    // measured over 400 puzzles across all modes, so most days take the first puzzle generated
+    fn target_score(&self) -> f64 {
+        match self {
+            Difficulty::Simple => 0.25,
+            Difficulty::Easy => 0.26,
+            Difficulty::Normal => 0.29,
+            Difficulty::Hard => 0.33,
+            Difficulty::Lunatic => 0.43,
+            Difficulty::Lunatic2 => 0.57,
+        }
+    }
+
+    // Share of games players are expected to solve at this difficulty
+    fn target_solve_rate(&self) -> f64 {
+        match self {
+            Difficulty::Simple => 0.95,
+            Difficulty::Easy => 0.9,
+            Difficulty::Normal => 0.8,
+            Difficulty::Hard => 0.65,
+            Difficulty::Lunatic => 0.5,
+            Difficulty::Lunatic2 => 0.4,
+        }
+    }
+}
+
+impl ReqMode {
+    const ALL: [ReqMode; 4] = [
+        ReqMode::Classic,
+        ReqMode::Hidden,
+        ReqMode::WarmerColder,
+        ReqMode::Golf,
+    ];
+
+    // Same as the serialized name
+    fn name(&self) -> &'static str {
+        match self {
+            ReqMode::Classic => "classic",
+            ReqMode::Hidden => "hidden",
+            ReqMode::WarmerColder => "warmer_colder",
+            ReqMode::Golf => "golf",
+        }
+    }
 }
 
 impl ReqPuzzleOptions {
     fn to_puzzle_options(&self) -> PuzzleOptions {
-        let (num_hints, guarantee_answer_by) = match self.mode {
-            ReqMode::Classic => (4, 4),
-            ReqMode::Hidden => (8, 4),
+        // Hidden reveals its extra hints one by one, so each should help more than the last
+        let (num_hints, guarantee_answer_by, minimize_hints, hint_ordering) = match self.mode {
+            ReqMode::Classic => (4, 4, false, HintOrdering::Random),
+            ReqMode::Hidden | ReqMode::WarmerColder => (8, 4, false, HintOrdering::HardToEasy),
+            ReqMode::Golf => (4, 4, true, HintOrdering::Random),
         };
         match self.difficulty {
             Difficulty::Simple => PuzzleOptions {
@@ -182,90 +780,156 @@ impl ReqPuzzleOptions {
                 max_kanji_class: KanjiClass::Kyoiku,
                 rare_kanji_bias: 0.5,
                 fixed: None,
+                exclude: HashSet::new(),
+                theme: None,
                 min_word_kanji_class: KanjiClass::Kyoiku,
                 max_word_kanji_class: KanjiClass::Kyoiku,
                 min_word_rarity: 0,
                 max_word_rarity: 6_000,
+                exclude_words: HashSet::new(),
+                include_word_tags: HashSet::new(),
+                exclude_word_tags: HashSet::from([
+                    WordTag::Place,
+                    WordTag::Surname,
+                    WordTag::Sens,
+                    WordTag::Arch,
+                    WordTag::Obs,
+                ]),
                 irregular_hint_bias: 0.5,
                 rare_kanji_hint_bias: 0.5,
                 rare_word_hint_bias: 0.5,
                 num_hints,
                 guarantee_answer_by,
+                minimize_hints,
+                hint_ordering,
             },
             Difficulty::Easy => PuzzleOptions {
                 min_kanji_class: KanjiClass::Kyoiku,
                 max_kanji_class: KanjiClass::Kyoiku,
                 rare_kanji_bias: 1.0,
                 fixed: None,
+                exclude: HashSet::new(),
+                theme: None,
                 min_word_kanji_class: KanjiClass::Kyoiku,
                 max_word_kanji_class: KanjiClass::Kyoiku,
                 min_word_rarity: 0,
                 max_word_rarity: 12_000,
+                exclude_words: HashSet::new(),
+                include_word_tags: HashSet::new(),
+                exclude_word_tags: HashSet::from([
+                    WordTag::Place,
+                    WordTag::Surname,
+                    WordTag::Sens,
+                    WordTag::Arch,
+                    WordTag::Obs,
+                ]),
                 irregular_hint_bias: 1.0,
                 rare_kanji_hint_bias: 1.0,
                 rare_word_hint_bias: 1.0,
                 num_hints,
                 guarantee_answer_by,
+                minimize_hints,
+                hint_ordering,
             },
             Difficulty::Normal => PuzzleOptions {
                 min_kanji_class: KanjiClass::Kyoiku,
                 max_kanji_class: KanjiClass::Joyo,
                 rare_kanji_bias: 1.0,
                 fixed: None,
+                exclude: HashSet::new(),
+                theme: None,
                 min_word_kanji_class: KanjiClass::Kyoiku,
                 max_word_kanji_class: KanjiClass::Joyo,
                 min_word_rarity: 0,
                 max_word_rarity: 24_000,
+                exclude_words: HashSet::new(),
+                include_word_tags: HashSet::new(),
+                exclude_word_tags: HashSet::from([
+                    WordTag::Place,
+                    WordTag::Surname,
+                    WordTag::Sens,
+                    WordTag::Arch,
+                    WordTag::Obs,
+                ]),
                 irregular_hint_bias: 1.0,
                 rare_kanji_hint_bias: 1.0,
                 rare_word_hint_bias: 1.0,
                 num_hints,
                 guarantee_answer_by,
+                minimize_hints,
+                hint_ordering,
             },
             Difficulty::Hard => PuzzleOptions {
                 min_kanji_class: KanjiClass::Kyoiku,
                 max_kanji_class: KanjiClass::Joyo,
                 rare_kanji_bias: 2.0,
                 fixed: None,
+                exclude: HashSet::new(),
+                theme: None,
                 min_word_kanji_class: KanjiClass::Kyoiku,
                 max_word_kanji_class: KanjiClass::Joyo,
                 min_word_rarity: 0,
                 max_word_rarity: 48_000,
+                exclude_words: HashSet::new(),
+                include_word_tags: HashSet::new(),
+                exclude_word_tags: HashSet::from([
+                    WordTag::Place,
+                    WordTag::Surname,
+                    WordTag::Sens,
+                    WordTag::Arch,
+                    WordTag::Obs,
+                ]),
                 irregular_hint_bias: 2.0,
                 rare_kanji_hint_bias: 2.0,
                 rare_word_hint_bias: 2.0,
                 num_hints,
                 guarantee_answer_by,
+                minimize_hints,
+                hint_ordering,
             },
             Difficulty::Lunatic => PuzzleOptions {
                 min_kanji_class: KanjiClass::Joyo,
                 max_kanji_class: KanjiClass::Kentei,
                 rare_kanji_bias: 2.0,
                 fixed: None,
+                exclude: HashSet::new(),
+                theme: None,
                 min_word_kanji_class: KanjiClass::Kyoiku,
                 max_word_kanji_class: KanjiClass::Kentei,
                 min_word_rarity: 0,
                 max_word_rarity: 96_000,
+                exclude_words: HashSet::new(),
+                include_word_tags: HashSet::new(),
+                exclude_word_tags: HashSet::from([WordTag::Place, WordTag::Surname, WordTag::Sens]),
                 irregular_hint_bias: 2.0,
                 rare_kanji_hint_bias: 2.0,
                 rare_word_hint_bias: 2.0,
                 num_hints,
                 guarantee_answer_by,
+                minimize_hints,
+                hint_ordering,
             },
             Difficulty::Lunatic2 => PuzzleOptions {
                 min_kanji_class: KanjiClass::Joyo,
                 max_kanji_class: KanjiClass::All,
                 rare_kanji_bias: 2.0,
                 fixed: None,
+                exclude: HashSet::new(),
+                theme: None,
                 min_word_kanji_class: KanjiClass::Kyoiku,
                 max_word_kanji_class: KanjiClass::All,
                 min_word_rarity: 0,
                 max_word_rarity: MAX_WORD_RANK,
+                exclude_words: HashSet::new(),
+                include_word_tags: HashSet::new(),
+                exclude_word_tags: HashSet::from([WordTag::Place, WordTag::Surname, WordTag::Sens]),
                 irregular_hint_bias: 2.0,
                 rare_kanji_hint_bias: 2.0,
                 rare_word_hint_bias: 2.0,
                 num_hints,
                 guarantee_answer_by,
+                minimize_hints,
+                hint_ordering,
             },
         }
     }
@@ -273,22 +937,42 @@ impl ReqPuzzleOptions {
 
 #[derive(Debug, Serialize, Clone)]
 struct ResPuzzle {
+    id: u64,
     hints: Vec<ResHint>,
     extra_hints: Vec<ResHint>,
     answer: Ji,
     answer_meta: KanjiMeta,
     difficulty: Difficulty,
+    score: DifficultyScore,
+    // Kanji that fit all hints but one, for the reveal after a game
+    decoys: Vec<Candidate>,
+    meta_hints: [MetaHintKind; 3],
+    // Fewest of the hints that make the answer unique, and how many hints in reveal order it
+    // takes until it is
+    min_hints: Option<usize>,
+    determined_after: Option<usize>,
+    theme: Option<String>,
+    // Local date of a daily puzzle
+    date: Option<NaiveDate>,
 }
 
 impl ResPuzzle {
-    fn new_from_puzzle(
+    fn new_from_puzzle<R: rand::Rng>(
+        id: u64,
         puzzle: &Puzzle,
-        kanji_data: &KanjiData,
+        g: &Generator<'_, R>,
         difficulty: Difficulty,
     ) -> ResPuzzle {
+        let hints = puzzle.hints.iter().collect::<Vec<_>>();
         ResPuzzle {
+            id,
             answer: puzzle.answer,
-            answer_meta: kanji_data.kanji_metas.get(&puzzle.answer).unwrap().clone(),
+            answer_meta: g
+                .kanji_data
+                .kanji_metas
+                .get(&puzzle.answer)
+                .unwrap()
+                .clone(),
             hints: puzzle.hints.iter().map(ResHint::new_from_hint).collect(),
             extra_hints: puzzle
                 .extra_hints
@@ -296,6 +980,52 @@ impl ResPuzzle {
                 .map(ResHint::new_from_hint)
                 .collect(),
             difficulty,
+            score: g.estimate_difficulty(puzzle),
+            decoys: g.solvability_report(puzzle.answer, &hints).decoys,
+            meta_hints: MetaHintKind::ALL,
+            min_hints: g.minimal_hints(puzzle.answer, &hints, 1).map(|hs| hs.len()),
+            determined_after: g.determined_after(
+                puzzle.answer,
+                &puzzle
+                    .hints
+                    .iter()
+                    .chain(&puzzle.extra_hints)
+                    .collect::<Vec<_>>(),
+            ),
+            theme: None,
+            date: None,
+        }
+    }
+}
+
+// A puzzle without its answer, which is only given out once asked for
+#[derive(Debug, Serialize)]
+struct ResPuzzleV2 {
+    id: u64,
+    hints: Vec<ResHint>,
+    extra_hints: Vec<ResHint>,
+    difficulty: Difficulty,
+    score: DifficultyScore,
+    meta_hints: [MetaHintKind; 3],
+    min_hints: Option<usize>,
+    determined_after: Option<usize>,
+    theme: Option<String>,
+    date: Option<NaiveDate>,
+}
+
+impl From<ResPuzzle> for ResPuzzleV2 {
+    fn from(puzzle: ResPuzzle) -> ResPuzzleV2 {
+        ResPuzzleV2 {
+            id: puzzle.id,
+            hints: puzzle.hints,
+            extra_hints: puzzle.extra_hints,
+            difficulty: puzzle.difficulty,
+            score: puzzle.score,
+            meta_hints: puzzle.meta_hints,
+            min_hints: puzzle.min_hints,
+            determined_after: puzzle.determined_after,
+            theme: puzzle.theme,
+            date: puzzle.date,
         }
     }
 }
@@ -333,73 +1063,645 @@ async fn get_day(
         .unwrap()
         .duration_trunc(TimeDelta::days(1))
         .unwrap();
-    let difficulty = get_difficulty(today);
-    let seed = get_seed(today, payload.mode, difficulty);
-
-    let mut g = state.to_generator_seeded(seed);
-    let puzzle = ResPuzzle::new_from_puzzle(
-        &g.choose_puzzle(
-            &ReqPuzzleOptions {
-                mode: payload.mode,
-                difficulty,
-            }
-            .to_puzzle_options(),
-        ),
-        &state.kanji_data,
-        difficulty,
-    );
+    let scope = state.daily_schedule(payload.guild.as_deref()).await;
+    let puzzle = state
+        .cached_daily_res_puzzle(&scope, today, payload.mode)
+        .await;
+    state.remember_served(&puzzle).await;
     Ok(Json(puzzle))
 }
 
-const MAX_CACHE_LEN: usize = 2;
+#[cfg(feature = "debug-routes")]
+async fn get_solvability(
+    State(state): State<Arc<ApiState>>,
+    extract::Query(payload): extract::Query<ReqDayPuzzleOptions>,
+) -> Result<Json<generate::SolvabilityReport>, StatusCode> {
+    let day = DateTime::from_timestamp_millis(payload.date)
+        .unwrap()
+        .duration_trunc(TimeDelta::days(1))
+        .unwrap();
+    let scope = state.daily_schedule(payload.guild.as_deref()).await;
+    let puzzle = state.daily_puzzle(&scope, day, payload.mode).await;
+    let hints = puzzle.hints.iter().collect::<Vec<_>>();
+    Ok(Json(
+        state
+            .to_generator_random()
+            .solvability_report(puzzle.answer, &hints),
+    ))
+}
+
+const MAX_CACHE_LEN: usize = 64;
+const CACHE_TTL: Duration = Duration::from_secs(2 * 24 * 60 * 60);
+const MAX_SERVED_LEN: usize = 10_000;
 
 async fn get_today(
     State(state): State<Arc<ApiState>>,
     extract::Query(payload): extract::Query<ReqTodayPuzzleOptions>,
+    extract::Query(player): extract::Query<ReqPlayer>,
+) -> Result<Json<ResPuzzle>, StatusCode> {
+    let today = state
+        .local_today(payload.guild.as_deref(), payload.tz)
+        .await;
+    let scope = state.daily_schedule(payload.guild.as_deref()).await;
+    let puzzle = state
+        .cached_daily_res_puzzle(&scope, today, payload.mode)
+        .await;
+    // Cached puzzles may have dropped out of the served ones since
+    state.remember_served(&puzzle).await;
+    state.remember_answer(&player, puzzle.answer).await;
+    Ok(Json(puzzle))
+}
+
+async fn get_today_v2(
+    state: State<Arc<ApiState>>,
+    payload: extract::Query<ReqTodayPuzzleOptions>,
+    player: extract::Query<ReqPlayer>,
+) -> Result<Json<ResPuzzleV2>, StatusCode> {
+    let Json(puzzle) = get_today(state, payload, player).await?;
+    Ok(Json(puzzle.into()))
+}
+
+async fn get_random(
+    State(state): State<Arc<ApiState>>,
+    extract::Query(payload): extract::Query<ReqPuzzleOptions>,
+    extract::Query(player): extract::Query<ReqPlayer>,
+) -> Result<Json<ResPuzzle>, StatusCode> {
+    let mut options = state.calibrated_options(&payload).await?;
+    if let Some(user) = &player.user {
+        if let Some(p) = state.store.read().await.player(user) {
+            options.exclude = p.recent_answers.iter().copied().collect();
+        }
+    }
+
+    // The generator is not Send, so it has to be gone before awaiting
+    let puzzle = state.to_generator_random().choose_puzzle(&options);
+    let mut puzzle = state
+        .to_res_puzzle(rand::random(), &puzzle, payload.difficulty)
+        .await;
+    puzzle.theme = payload.theme;
+    state.remember_answer(&player, puzzle.answer).await;
+    Ok(Json(puzzle))
+}
+
+async fn get_random_v2(
+    state: State<Arc<ApiState>>,
+    payload: extract::Query<ReqPuzzleOptions>,
+    player: extract::Query<ReqPlayer>,
+) -> Result<Json<ResPuzzleV2>, StatusCode> {
+    let Json(puzzle) = get_random(state, payload, player).await?;
+    Ok(Json(puzzle.into()))
+}
+
+async fn get_review(
+    State(state): State<Arc<ApiState>>,
+    extract::Query(payload): extract::Query<ReqPuzzleOptions>,
+    extract::Query(player): extract::Query<ReqPlayer>,
 ) -> Result<Json<ResPuzzle>, StatusCode> {
-    let today = Utc::now().duration_trunc(TimeDelta::days(1)).unwrap();
-    let difficulty = get_difficulty(today);
-    let seed = get_seed(today, payload.mode, difficulty);
+    let today = get_local_day(Utc::now(), player.tz.unwrap_or(Tz::UTC));
+    let user = player.user.as_ref().ok_or(StatusCode::BAD_REQUEST)?;
+    let due = state
+        .store
+        .read()
+        .await
+        .player(user)
+        .map(|p| p.due_reviews(today))
+        .unwrap_or_default();
 
-    if let Some(puzzle) = state.cache.read().await.get(&seed) {
-        tracing::debug!("Using cache for puzzle {}", seed);
-        return Ok(Json(puzzle.clone()));
+    for answer in due {
+        let mut options = state.calibrated_options(&payload).await?;
+        options.fixed = Some(answer);
+        // The generator falls back to other kanji if the due one cannot make a puzzle at this
+        // difficulty, so try the next one instead
+        let puzzle = state.to_generator_random().choose_puzzle(&options);
+        if puzzle.answer == answer {
+            return Ok(Json(
+                state
+                    .to_res_puzzle(rand::random(), &puzzle, payload.difficulty)
+                    .await,
+            ));
+        }
     }
+    Err(StatusCode::NOT_FOUND)
+}
 
-    let mut g = state.to_generator_seeded(seed);
-    let puzzle = ResPuzzle::new_from_puzzle(
-        &g.choose_puzzle(
-            &ReqPuzzleOptions {
+async fn post_result(
+    State(state): State<Arc<ApiState>>,
+    Json(payload): Json<ReqResult>,
+) -> Result<StatusCode, StatusCode> {
+    if payload.attempts > calibrate::MAX_ATTEMPTS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let puzzle = match payload.id {
+        Some(id) => match state.served_answer(id).await {
+            Ok(answer) if answer != payload.answer => return Err(StatusCode::BAD_REQUEST),
+            Ok(_) => Some(id),
+            Err(_) => None,
+        },
+        None => None,
+    };
+    let of = ResultOf {
+        difficulty: payload.difficulty,
+        date: payload.date,
+        mode: payload.mode.map(|m| m.name()),
+        puzzle,
+    };
+    let today = get_local_day(Utc::now(), payload.tz.unwrap_or(Tz::UTC));
+    let mut store = state.store.write().await;
+    store.player_mut(&payload.user).record_result(
+        payload.answer,
+        payload.solved,
+        payload.attempts,
+        today,
+    );
+    store
+        .stats
+        .record(payload.answer, &of, payload.solved, payload.attempts);
+    store.save().map_err(|e| {
+        tracing::error!("Could not save store: {e:?}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Serialize)]
+struct ResGuess {
+    verdict: Verdict,
+    correct: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    feedback: Option<Feedback>,
+}
+
+async fn get_guess(
+    State(state): State<Arc<ApiState>>,
+    extract::Query(payload): extract::Query<ReqGuess>,
+) -> Json<ResGuess> {
+    Json(state.guess(payload).await)
+}
+
+// Guesses at a served puzzle by its id, for clients that were not given the answer
+async fn get_puzzle_guess(
+    State(state): State<Arc<ApiState>>,
+    Path(id): Path<u64>,
+    extract::Query(payload): extract::Query<ReqPuzzleGuess>,
+) -> Result<Json<ResGuess>, StatusCode> {
+    let answer = state.served_answer(id).await?;
+    Ok(Json(
+        state
+            .guess(ReqGuess {
+                answer,
+                guess: payload.guess,
+                guild: payload.guild,
                 mode: payload.mode,
-                difficulty,
+            })
+            .await,
+    ))
+}
+
+#[derive(Debug, Serialize)]
+struct ResAnswer {
+    answer: Ji,
+    answer_meta: KanjiMeta,
+    decoys: Vec<Candidate>,
+}
+
+// For the reveal once a game is over
+async fn get_puzzle_answer(
+    State(state): State<Arc<ApiState>>,
+    Path(id): Path<u64>,
+) -> Result<Json<ResAnswer>, StatusCode> {
+    let (answer, decoys) = state
+        .served
+        .read()
+        .await
+        .get(&id)
+        .map(|p| (p.answer, p.decoys.clone()))
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(ResAnswer {
+        answer,
+        answer_meta: state.answer_meta(answer)?.clone(),
+        decoys,
+    }))
+}
+
+impl ApiState {
+    async fn guess(&self, payload: ReqGuess) -> ResGuess {
+        let policy = match &payload.guild {
+            Some(guild) => self
+                .store
+                .read()
+                .await
+                .guild(guild)
+                .map(|g| g.variant_policy)
+                .unwrap_or_default(),
+            None => VariantPolicy::default(),
+        };
+        let verdict = guess::evaluate(&self.data.kanji_data, payload.answer, payload.guess, policy);
+        let feedback = match payload.mode {
+            Some(ReqMode::WarmerColder) if !verdict.is_correct() => {
+                guess::feedback(&self.data.kanji_data, payload.answer, payload.guess)
             }
-            .to_puzzle_options(),
-        ),
-        &state.kanji_data,
-        difficulty,
-    );
+            _ => None,
+        };
+        ResGuess {
+            verdict,
+            correct: verdict.is_correct(),
+            feedback,
+        }
+    }
 
-    let mut cache = state.cache.write().await;
-    if cache.len() >= MAX_CACHE_LEN {
-        let (k, _) = cache.pop_first().unwrap();
-        tracing::debug!("Removed from cache puzzle {}", k);
+    async fn served_answer(&self, id: u64) -> Result<Ji, StatusCode> {
+        self.served
+            .read()
+            .await
+            .get(&id)
+            .map(|p| p.answer)
+            .ok_or(StatusCode::NOT_FOUND)
     }
-    cache.insert(seed, puzzle.clone());
 
-    Ok(Json(puzzle))
+    // Answers of archived daily puzzles may be gone from the current data
+    fn answer_meta(&self, answer: Ji) -> Result<&KanjiMeta, StatusCode> {
+        std::iter::once(&self.data)
+            .chain(&self.archive)
+            .find_map(|d| d.kanji_data.kanji_metas.get(&answer))
+            .ok_or(StatusCode::NOT_FOUND)
+    }
 }
 
-async fn get_random(
+// Guild settings change what every member is served, so only admins may change them
+async fn post_guild(
     State(state): State<Arc<ApiState>>,
-    extract::Query(payload): extract::Query<ReqPuzzleOptions>,
-) -> Result<Json<ResPuzzle>, StatusCode> {
-    let mut g = state.to_generator_random();
-    let puzzle = ResPuzzle::new_from_puzzle(
-        &g.choose_puzzle(&payload.to_puzzle_options()),
-        &state.kanji_data,
-        payload.difficulty,
+    headers: HeaderMap,
+    Json(payload): Json<ReqGuild>,
+) -> Result<StatusCode, StatusCode> {
+    check_admin(&state, &headers)?;
+    let mut store = state.store.write().await;
+    let guild = store.guild_mut(&payload.guild);
+    if let Some(variant_policy) = payload.variant_policy {
+        guild.variant_policy = variant_policy;
+    }
+    if let Some(schedule) = payload.schedule {
+        let today = schedule::latest_today();
+        let (old, schedule) = match &guild.schedule {
+            Some(old) => (old.clone(), schedule),
+            // Until now the guild followed the shared schedule, so its past days keep to that
+            None if !schedule.versions.is_empty() => (
+                Schedule {
+                    versions: state.schedule.started(today),
+                },
+                schedule.branched_from(&state.schedule, today),
+            ),
+            None => (Schedule::default(), schedule),
+        };
+        schedule
+            .check_update(&old, today)
+            .map_err(|_| StatusCode::BAD_REQUEST)?;
+        guild.schedule = (!schedule.versions.is_empty()).then_some(schedule);
+        guild.forget_daily_answers_from(today.succ_opt().unwrap());
+    }
+    if let Some(timezone) = payload.timezone {
+        guild.timezone = Some(timezone).filter(|&tz| tz != Tz::UTC);
+    }
+    store.save().map_err(|e| {
+        tracing::error!("Could not save store: {e:?}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Serialize)]
+struct ResMetaHint {
+    kind: MetaHintKind,
+    value: String,
+}
+
+// Meta hints are revealed in order, so asking for one also gives the ones before it. Kinds
+// the answer has no value for, like the on-reading of a kanji made in Japan, are left out.
+async fn get_meta_hint(
+    State(state): State<Arc<ApiState>>,
+    Path(id): Path<u64>,
+    extract::Query(payload): extract::Query<ReqMetaHint>,
+) -> Result<Json<Vec<ResMetaHint>>, StatusCode> {
+    let answer = state.served_answer(id).await?;
+    let meta = state.answer_meta(answer)?;
+    let hints = MetaHintKind::ALL
+        .into_iter()
+        .take_while(|&kind| kind <= payload.kind)
+        .filter_map(|kind| {
+            Some(ResMetaHint {
+                kind,
+                value: kind.reveal(meta)?,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // Only what this player had not been given yet is counted
+    let before = {
+        let mut served = state.served.write().await;
+        let Some(puzzle) = served.get_mut(&id) else {
+            return Ok(Json(hints));
+        };
+        let before = puzzle.revealed.get(&payload.user).copied();
+        if before.is_some_and(|kind| kind >= payload.kind) {
+            return Ok(Json(hints));
+        }
+        puzzle.revealed.insert(payload.user, payload.kind);
+        before
+    };
+    let mut store = state.store.write().await;
+    store.stats.record_reveals(
+        id,
+        hints
+            .iter()
+            .map(|h| h.kind)
+            .filter(|&kind| before.is_none_or(|b| kind > b)),
     );
-    Ok(Json(puzzle))
+    store.mark_dirty();
+
+    Ok(Json(hints))
+}
+
+const DEFAULT_LOOKUP_COMPOUNDS: usize = 20;
+const MAX_LOOKUP_COMPOUNDS: usize = 100;
+
+#[derive(Debug, Serialize)]
+struct ResKanji {
+    ji: Ji,
+    rank: usize,
+    count: usize,
+    meta: KanjiMeta,
+    // SVG path data per stroke in stroke order, empty without KanjiVG data
+    stroke_paths: Vec<String>,
+    // Most common compounds first, split by which side the kanji is on
+    compounds_l: Vec<ResCompound>,
+    compounds_r: Vec<ResCompound>,
+}
+
+#[derive(Debug, Serialize)]
+struct ResCompound {
+    text: String,
+    reading: String,
+    rank: usize,
+}
+
+async fn get_kanji(
+    State(state): State<Arc<ApiState>>,
+    Path(ji): Path<char>,
+    extract::Query(payload): extract::Query<ReqLookup>,
+) -> Result<Json<ResKanji>, StatusCode> {
+    let ji = Ji(ji);
+    let (Some(kanji), Some(meta)) = (
+        state.data.kanji_data.kanjis.get(&ji),
+        state.data.kanji_data.kanji_metas.get(&ji),
+    ) else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let limit = payload
+        .limit
+        .unwrap_or(DEFAULT_LOOKUP_COMPOUNDS)
+        .min(MAX_LOOKUP_COMPOUNDS);
+    let blocked = state.blocked_words.read().await;
+    let compounds = |loc: Loc| {
+        state
+            .data
+            .word_data
+            .compounds_of(ji, loc, &blocked)
+            .take(limit)
+            .map(|two| ResCompound {
+                text: two.word.text.clone(),
+                reading: two.word.reading.clone(),
+                rank: two.word.rank,
+            })
+            .collect::<Vec<_>>()
+    };
+    Ok(Json(ResKanji {
+        ji,
+        rank: kanji.rank,
+        count: kanji.count,
+        meta: meta.clone(),
+        stroke_paths: state.stroke_paths.get(&ji).cloned().unwrap_or_default(),
+        compounds_l: compounds(Loc::L),
+        compounds_r: compounds(Loc::R),
+    }))
+}
+
+#[derive(Debug, Serialize)]
+struct ResWord {
+    text: String,
+    reading: String,
+    rank: usize,
+    irregularness: f64,
+    a: Ji,
+    a_meta: KanjiMeta,
+    b: Ji,
+    b_meta: KanjiMeta,
+    // Most common other compounds with the same left or the same right kanji
+    siblings_a: Vec<ResCompound>,
+    siblings_b: Vec<ResCompound>,
+}
+
+async fn get_word(
+    State(state): State<Arc<ApiState>>,
+    Path(text): Path<String>,
+    extract::Query(payload): extract::Query<ReqLookup>,
+) -> Result<Json<ResWord>, StatusCode> {
+    let blocked = state.blocked_words.read().await;
+    let two = state
+        .data
+        .word_data
+        .twos
+        .get(&text)
+        .filter(|_| !blocked.contains_key(&text))
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let limit = payload
+        .limit
+        .unwrap_or(DEFAULT_LOOKUP_COMPOUNDS)
+        .min(MAX_LOOKUP_COMPOUNDS);
+    let siblings = |loc: Loc| {
+        state
+            .data
+            .word_data
+            .siblings_of(two, loc, &blocked)
+            .take(limit)
+            .map(|x| ResCompound {
+                text: x.word.text.clone(),
+                reading: x.word.reading.clone(),
+                rank: x.word.rank,
+            })
+            .collect::<Vec<_>>()
+    };
+    let meta = |ji: Ji| state.data.kanji_data.kanji_metas.get(&ji).unwrap().clone();
+    Ok(Json(ResWord {
+        text: two.word.text.clone(),
+        reading: two.word.reading.clone(),
+        rank: two.word.rank,
+        irregularness: two.irregularness,
+        a: two.a,
+        a_meta: meta(two.a),
+        b: two.b,
+        b_meta: meta(two.b),
+        siblings_a: siblings(Loc::L),
+        siblings_b: siblings(Loc::R),
+    }))
+}
+
+fn check_admin(state: &ApiState, headers: &HeaderMap) -> Result<(), StatusCode> {
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| x.strip_prefix("Bearer "));
+    match (&state.admin_token, token) {
+        (Some(expected), Some(token)) if expected == token => Ok(()),
+        _ => Err(StatusCode::FORBIDDEN),
+    }
+}
+
+// Picks up days locked with the schedule command since the server started
+async fn post_calendar_reload(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+) -> Result<StatusCode, StatusCode> {
+    check_admin(&state, &headers)?;
+    let path = env::var("KDLE_CALENDAR_PATH").unwrap_or_else(|_| "calendar.json".to_owned());
+    let calendar = Calendar::load(path).map_err(|e| {
+        tracing::error!("Could not load calendar: {e:?}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    // Answers and puzzles made before may differ from what was locked, which is only ever a day
+    // no one has played yet
+    *state.calendar.write().await = calendar;
+    let tomorrow = schedule::latest_today().succ_opt().unwrap();
+    state
+        .store
+        .write()
+        .await
+        .forget_daily_answers_from(tomorrow);
+    state.cache.clear();
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Blocks a word, or every compound with a kanji, from being used as a hint right away, and in
+// every later data build
+async fn post_blocklist(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+    Json(payload): Json<ReqBlock>,
+) -> Result<StatusCode, StatusCode> {
+    check_admin(&state, &headers)?;
+    let twos = &state.data.word_data.twos;
+    let mut words = match payload.text.chars().collect::<Vec<_>>()[..] {
+        [ji] if state.data.kanji_data.kanjis.contains_key(&Ji(ji)) => {
+            // Allowlisted words are kept whatever kanji they have, as in a build
+            let curation = Curation::load().map_err(|e| {
+                tracing::error!("Could not read curation lists: {e:?}");
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+            twos.values()
+                .filter(|two| two.a == Ji(ji) || two.b == Ji(ji))
+                .filter(|two| !curation.allowed.contains_key(&two.word.text))
+                .map(|two| two.word.text.clone())
+                .collect::<Vec<_>>()
+        }
+        _ if twos.contains_key(&payload.text) => vec![payload.text.clone()],
+        _ => return Err(StatusCode::NOT_FOUND),
+    };
+
+    // Days someone may already be playing keep their puzzles
+    let since = schedule::latest_today().succ_opt().unwrap();
+    let mut blocked = state.blocked_words.write().await;
+    words.retain(|text| !blocked.contains_key(text));
+    if !words.is_empty() {
+        data::append_to_blocklist(&payload.text, payload.reason, since).map_err(|e| {
+            tracing::error!("Could not write blocklist: {e:?}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        blocked.extend(words.into_iter().map(|text| (text, since)));
+        // Daily puzzles take the store lock after this one, so holding both here could deadlock
+        drop(blocked);
+        // Puzzles already made for later days may use the word
+        state.store.write().await.forget_daily_answers_from(since);
+        state.cache.clear();
+        tracing::info!("Blocked {} ({}) from {since}", payload.text, payload.reason);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn get_themes(State(state): State<Arc<ApiState>>) -> Json<Themes> {
+    Json(state.data.themes.clone())
+}
+
+// Manifests of every loaded data build, oldest first
+async fn get_data_versions(State(state): State<Arc<ApiState>>) -> Json<Vec<DataManifest>> {
+    Json(
+        state
+            .archive
+            .iter()
+            .chain(std::iter::once(&state.data))
+            .map(|d| d.manifest.clone())
+            .collect(),
+    )
+}
+
+const MAX_REPORT_ANSWERS: usize = 50;
+// Changes that are only marked in the store are saved this often
+const STORE_FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Serialize)]
+struct ResCalibration {
+    difficulties: Vec<ResDifficultyReport>,
+    // Answers with the lowest solve rate first
+    hardest_answers: Vec<ResAnswerReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct ResDifficultyReport {
+    difficulty: Difficulty,
+    played: usize,
+    solve_rate: f64,
+    mean_attempts: f64,
+    target_solve_rate: f64,
+    adjustment: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct ResAnswerReport {
+    answer: Ji,
+    played: usize,
+    solve_rate: f64,
+    mean_attempts: f64,
+}
+
+async fn get_calibration(State(state): State<Arc<ApiState>>) -> Json<ResCalibration> {
+    let store = state.store.read().await;
+    let difficulties = store
+        .stats
+        .by_difficulty
+        .iter()
+        .map(|(&difficulty, tally)| ResDifficultyReport {
+            difficulty,
+            played: tally.played,
+            solve_rate: tally.solve_rate(),
+            mean_attempts: tally.mean_attempts(),
+            target_solve_rate: difficulty.target_solve_rate(),
+            adjustment: tally.adjustment(difficulty.target_solve_rate()),
+        })
+        .collect();
+    let mut hardest_answers = store
+        .stats
+        .by_answer
+        .iter()
+        .map(|(&answer, tally)| ResAnswerReport {
+            answer,
+            played: tally.played,
+            solve_rate: tally.solve_rate(),
+            mean_attempts: tally.mean_attempts(),
+        })
+        .collect::<Vec<_>>();
+    hardest_answers.sort_by(|a, b| a.solve_rate.total_cmp(&b.solve_rate));
+    hardest_answers.truncate(MAX_REPORT_ANSWERS);
+    Json(ResCalibration {
+        difficulties,
+        hardest_answers,
+    })
 }
 
 #[cfg(feature = "debug-routes")]
@@ -407,25 +1709,33 @@ async fn get_fixed(
     State(state): State<Arc<ApiState>>,
     extract::Query(payload): extract::Query<ReqPuzzleWithAnswerPuzzleOptions>,
 ) -> Result<Json<ResPuzzle>, StatusCode> {
-    let mut g = state.to_generator_random();
-    let puzzle = ResPuzzle::new_from_puzzle(
-        &g.choose_puzzle(&{
-            let mut opts = ReqPuzzleOptions {
-                mode: payload.mode,
-                difficulty: payload.difficulty,
-            }
-            .to_puzzle_options();
-            opts.fixed = Some(payload.answer);
-            opts
-        }),
-        &state.kanji_data,
-        payload.difficulty,
-    );
+    let puzzle = state.to_generator_random().choose_puzzle(&{
+        let mut opts = ReqPuzzleOptions {
+            mode: payload.mode,
+            difficulty: payload.difficulty,
+            theme: None,
+        }
+        .to_puzzle_options();
+        opts.fixed = Some(payload.answer);
+        opts
+    });
+    let puzzle = state
+        .to_res_puzzle(rand::random(), &puzzle, payload.difficulty)
+        .await;
     Ok(Json(puzzle))
 }
 
-fn get_seed(date: DateTime<Utc>, mode: ReqMode, difficulty: Difficulty) -> u64 {
-    date.timestamp_millis() as u64 + (100 * (mode as u64) + (difficulty as u64))
+fn get_seed(date: DateTime<Utc>, mode: ReqMode, difficulty: Difficulty, data_version: &str) -> u64 {
+    seed::daily_seed(date, mode.name(), difficulty.name(), data_version)
+}
+
+// Midnight UTC of the date it is in the timezone, which is what daily puzzles are keyed by
+fn get_local_day(now: DateTime<Utc>, tz: Tz) -> DateTime<Utc> {
+    now.with_timezone(&tz)
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc()
 }
 
 fn get_difficulty(day: DateTime<Utc>) -> Difficulty {
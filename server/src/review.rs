@@ -0,0 +1,83 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+static MIN_EASINESS: f64 = 1.3;
+
+// https://super-memory.com/english/ol/sm2.htm
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ReviewItem {
+    pub easiness: f64,
+    pub repetitions: usize,
+    pub interval_days: i64,
+    pub due: DateTime<Utc>,
+}
+
+impl ReviewItem {
+    pub fn new(today: DateTime<Utc>) -> ReviewItem {
+        ReviewItem {
+            easiness: 2.5,
+            repetitions: 0,
+            interval_days: 1,
+            due: today + TimeDelta::days(1),
+        }
+    }
+
+    pub fn is_due(&self, today: DateTime<Utc>) -> bool {
+        self.due <= today
+    }
+
+    // Quality is on the SM-2 scale of 0 to 5, anything below 3 is a failure
+    pub fn review(&mut self, quality: u8, today: DateTime<Utc>) {
+        let q = quality.min(5) as f64;
+        if quality < 3 {
+            self.repetitions = 0;
+            self.interval_days = 1;
+        } else {
+            self.interval_days = match self.repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (self.interval_days as f64 * self.easiness).round() as i64,
+            };
+            self.repetitions += 1;
+        }
+        self.easiness =
+            (self.easiness + 0.1 - (5.0 - q) * (0.08 + (5.0 - q) * 0.02)).max(MIN_EASINESS);
+        self.due = today + TimeDelta::days(self.interval_days);
+    }
+}
+
+// Maps the outcome of a game to an SM-2 quality
+pub fn quality(solved: bool, attempts: usize) -> u8 {
+    match (solved, attempts) {
+        (false, _) => 1,
+        (true, 0..=1) => 5,
+        (true, 2) => 4,
+        (true, _) => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intervals_grow_and_reset() {
+        let today = DateTime::from_timestamp_millis(0).unwrap();
+        let mut item = ReviewItem::new(today);
+        assert!(!item.is_due(today));
+        assert!(item.is_due(today + TimeDelta::days(1)));
+
+        let intervals = (0..4)
+            .map(|_| {
+                item.review(5, today);
+                item.interval_days
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(intervals, [1, 6, 16, 45]);
+
+        item.review(1, today);
+        assert_eq!(item.repetitions, 0);
+        assert_eq!(item.interval_days, 1);
+        assert!(item.easiness >= MIN_EASINESS);
+    }
+}
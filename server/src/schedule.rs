@@ -0,0 +1,249 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Datelike, NaiveDate, TimeDelta, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::Difficulty;
+
+// Each version applies from its date on, so changing the schedule never changes a past day
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Schedule {
+    #[serde(default)]
+    pub versions: Vec<ScheduleVersion>,
+}
+
+// Overrides win over the rotation, which wins over the weekday rules
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ScheduleVersion {
+    pub since: NaiveDate,
+    #[serde(default)]
+    pub weekdays: HashMap<Weekday, Difficulty>,
+    #[serde(default)]
+    pub rotation: Option<Rotation>,
+    #[serde(default)]
+    pub overrides: BTreeMap<NaiveDate, DayRule>,
+    // One theme per week, cycling from the week of since
+    #[serde(default)]
+    pub weekly_themes: Vec<String>,
+}
+
+// Difficulties cycling day by day from start, nothing before it
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Rotation {
+    pub start: NaiveDate,
+    pub pattern: Vec<Difficulty>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct DayRule {
+    pub difficulty: Option<Difficulty>,
+    pub theme: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DaySpec {
+    pub difficulty: Difficulty,
+    pub theme: Option<String>,
+}
+
+impl Schedule {
+    pub fn load(path: impl AsRef<Path>) -> Result<Schedule> {
+        let path = path.as_ref();
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                tracing::info!("No schedule found at {path:?}, using the default one");
+                return Ok(Schedule::default());
+            }
+            Err(e) => return Err(e.into()),
+        };
+        Ok(serde_json::from_reader(BufReader::new(file))?)
+    }
+
+    // Versions that already apply must stay as they were, only ones starting after today may
+    // be added, changed or removed
+    pub fn check_update(&self, old: &Schedule, today: NaiveDate) -> Result<()> {
+        if self.started(today) != old.started(today) {
+            bail!(
+                "Schedule versions may only be added or changed from {} on",
+                today.succ_opt().unwrap()
+            );
+        }
+        Ok(())
+    }
+
+    // Versions that already apply, oldest first
+    pub fn started(&self, today: NaiveDate) -> Vec<ScheduleVersion> {
+        let mut vs = self
+            .versions
+            .iter()
+            .filter(|v| v.since <= today)
+            .cloned()
+            .collect::<Vec<_>>();
+        vs.sort_by_key(|v| v.since);
+        vs
+    }
+
+    // This schedule on top of the versions of another that already apply, for a guild that
+    // followed that one until now
+    pub fn branched_from(&self, base: &Schedule, today: NaiveDate) -> Schedule {
+        let mut versions = base.started(today);
+        versions.retain(|v| !self.versions.contains(v));
+        versions.extend(self.versions.iter().cloned());
+        Schedule { versions }
+    }
+
+    pub fn day(&self, day: DateTime<Utc>) -> DaySpec {
+        let date = day.date_naive();
+        let default = DaySpec {
+            difficulty: crate::get_difficulty(day),
+            theme: None,
+        };
+        let Some(version) = self
+            .versions
+            .iter()
+            .filter(|v| v.since <= date)
+            .max_by_key(|v| v.since)
+        else {
+            return default;
+        };
+
+        let rule = version.overrides.get(&date);
+        let difficulty = rule
+            .and_then(|r| r.difficulty)
+            .or_else(|| version.rotation.as_ref().and_then(|r| r.on(date)))
+            .or_else(|| version.weekdays.get(&date.weekday()).copied())
+            .unwrap_or(default.difficulty);
+        let theme = rule.and_then(|r| r.theme.clone()).or_else(|| {
+            if version.weekly_themes.is_empty() {
+                return None;
+            }
+            let weeks = (monday(date) - monday(version.since)).num_weeks();
+            let i = weeks.rem_euclid(version.weekly_themes.len() as i64) as usize;
+            Some(version.weekly_themes[i].clone())
+        });
+        DaySpec { difficulty, theme }
+    }
+}
+
+impl Rotation {
+    fn on(&self, date: NaiveDate) -> Option<Difficulty> {
+        if self.pattern.is_empty() || date < self.start {
+            return None;
+        }
+        let i = (date - self.start)
+            .num_days()
+            .rem_euclid(self.pattern.len() as i64) as usize;
+        Some(self.pattern[i])
+    }
+}
+
+// The date in the timezone furthest ahead, UTC+14, so no player has seen a later day yet
+pub fn latest_today() -> NaiveDate {
+    (Utc::now() + TimeDelta::hours(14)).date_naive()
+}
+
+fn monday(date: NaiveDate) -> NaiveDate {
+    date - TimeDelta::days(date.weekday().num_days_from_monday() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(y: i32, m: u32, d: u32) -> DateTime<Utc> {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc()
+    }
+
+    #[test]
+    fn versions_keep_past_days() {
+        let schedule: Schedule = serde_json::from_str(
+            r#"{"versions": [{
+                "since": "2026-11-02",
+                "weekdays": {"Sat": "hard"},
+                "rotation": {"start": "2026-11-09", "pattern": ["easy", "lunatic"]},
+                "overrides": {"2026-11-10": {"difficulty": "simple", "theme": "numbers"}},
+                "weekly_themes": ["weather", "body"]
+            }]}"#,
+        )
+        .unwrap();
+
+        // Before the first version the built-in weekdays apply
+        assert_eq!(
+            schedule.day(day(2026, 10, 31)),
+            DaySpec {
+                difficulty: Difficulty::Lunatic,
+                theme: None
+            }
+        );
+        assert_eq!(schedule.day(day(2026, 11, 7)).difficulty, Difficulty::Hard);
+        assert_eq!(
+            schedule.day(day(2026, 11, 4)).difficulty,
+            Difficulty::Normal
+        );
+        assert_eq!(
+            schedule.day(day(2026, 11, 4)).theme.as_deref(),
+            Some("weather")
+        );
+        assert_eq!(schedule.day(day(2026, 11, 11)).difficulty, Difficulty::Easy);
+        assert_eq!(
+            schedule.day(day(2026, 11, 12)).difficulty,
+            Difficulty::Lunatic
+        );
+        assert_eq!(
+            schedule.day(day(2026, 11, 10)),
+            DaySpec {
+                difficulty: Difficulty::Simple,
+                theme: Some("numbers".to_owned())
+            }
+        );
+        assert_eq!(
+            schedule.day(day(2026, 11, 12)).theme.as_deref(),
+            Some("body")
+        );
+    }
+
+    #[test]
+    fn only_future_versions_change() {
+        let version = |since: &str| {
+            serde_json::from_str::<ScheduleVersion>(&format!(
+                r#"{{"since": "{since}", "weekdays": {{"Sat": "hard"}}}}"#
+            ))
+            .unwrap()
+        };
+        let today = NaiveDate::from_ymd_opt(2026, 11, 5).unwrap();
+        let old = Schedule {
+            versions: vec![version("2026-11-02")],
+        };
+        let added = Schedule {
+            versions: vec![version("2026-11-02"), version("2026-11-06")],
+        };
+        assert!(added.check_update(&old, today).is_ok());
+        assert!(old.check_update(&added, today).is_ok());
+        let backdated = Schedule {
+            versions: vec![version("2026-11-02"), version("2026-11-05")],
+        };
+        assert!(backdated.check_update(&old, today).is_err());
+        assert!(Schedule::default().check_update(&old, today).is_err());
+
+        let own = Schedule {
+            versions: vec![version("2026-11-06")],
+        };
+        let branched = own.branched_from(&old, today);
+        assert_eq!(branched, added);
+        assert!(branched.check_update(&old, today).is_ok());
+        assert_eq!(added.branched_from(&old, today), added);
+        assert!(backdated
+            .branched_from(&old, today)
+            .check_update(&old, today)
+            .is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use chrono::{DateTime, Utc};
+
+static SEED_NAMESPACE: &str = "kanjidle/daily/v2";
+static GUILD_ID_NAMESPACE: &str = "kanjidle/daily/guild";
+// 2026-10-21, earlier daily puzzles were seeded by adding the enum discriminants to the date
+pub const SEED_V2_SINCE: i64 = 1_792_540_800_000;
+
+// Seed of the daily puzzle for a date, by the serialized names of its mode and difficulty
+pub fn daily_seed(date: DateTime<Utc>, mode: &str, difficulty: &str, data_version: &str) -> u64 {
+    if date.timestamp_millis() < SEED_V2_SINCE {
+        if let Some(seed) = legacy_seed(date, mode, difficulty) {
+            return seed;
+        }
+    }
+    let key = [
+        SEED_NAMESPACE,
+        &date.format("%Y-%m-%d").to_string(),
+        mode,
+        difficulty,
+        data_version,
+    ]
+    .join("\0");
+    splitmix64(fnv1a(key.as_bytes()))
+}
+
+// Id of a daily puzzle made for a guild with a schedule of its own, which does not collide
+// with the shared puzzle of the same seed or that of another guild or theme
+pub fn guild_puzzle_id(seed: u64, guild: &str, theme: Option<&str>) -> u64 {
+    let key = [
+        GUILD_ID_NAMESPACE,
+        &seed.to_string(),
+        guild,
+        theme.unwrap_or_default(),
+    ]
+    .join("\0");
+    splitmix64(fnv1a(key.as_bytes()))
+}
+
+// The discriminants as they were when the old scheme was in use, so reordering the enums
+// does not change past puzzles. Modes added later never had a daily puzzle under it.
+fn legacy_seed(date: DateTime<Utc>, mode: &str, difficulty: &str) -> Option<u64> {
+    let mode = match mode {
+        "classic" => 0,
+        "hidden" => 1,
+        "warmer_colder" => 2,
+        "golf" => 3,
+        _ => return None,
+    };
+    let difficulty = match difficulty {
+        "simple" => 0,
+        "easy" => 1,
+        "normal" => 2,
+        "hard" => 3,
+        "lunatic" => 4,
+        "lunatic2" => 5,
+        _ => return None,
+    };
+    Some(date.timestamp_millis() as u64 + (100 * mode + difficulty))
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |h, &b| {
+        (h ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use itertools::Itertools;
+
+    use super::*;
+
+    fn day(y: i32, m: u32, d: u32) -> DateTime<Utc> {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc()
+    }
+
+    // Published puzzles depend on these, they must never change
+    #[test]
+    fn seeds_are_pinned() {
+        assert_eq!(
+            daily_seed(day(2024, 10, 1), "classic", "normal", "1"),
+            1_727_740_800_002
+        );
+        assert_eq!(
+            daily_seed(day(2026, 10, 20), "hidden", "lunatic2", "1"),
+            1_792_454_400_105
+        );
+        assert_eq!(
+            daily_seed(day(2026, 10, 21), "classic", "normal", "1"),
+            11_328_136_428_388_195_755
+        );
+        assert_eq!(
+            daily_seed(day(2026, 10, 21), "hidden", "normal", "1"),
+            1_303_187_819_231_148_422
+        );
+        assert_eq!(
+            daily_seed(day(2026, 10, 24), "golf", "lunatic", "1"),
+            8_066_234_748_291_755_179
+        );
+        assert_eq!(
+            daily_seed(day(2026, 10, 21), "classic", "normal", "2"),
+            12_714_010_443_410_939_511
+        );
+    }
+
+    #[test]
+    fn guild_ids_are_scoped() {
+        let seed = daily_seed(day(2026, 11, 2), "classic", "normal", "1");
+        let ids = [
+            guild_puzzle_id(seed, "a", None),
+            guild_puzzle_id(seed, "b", None),
+            guild_puzzle_id(seed, "a", Some("animals")),
+        ];
+        assert!(!ids.contains(&seed));
+        assert!(ids.iter().all_unique());
+        assert_eq!(ids[0], guild_puzzle_id(seed, "a", None));
+    }
+}
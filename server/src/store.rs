@@ -0,0 +1,264 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fs::{self, File},
+    io::{BufReader, BufWriter, Write},
+    path::PathBuf,
+};
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use crate::calibrate::Stats;
+use crate::data::Ji;
+use crate::guess::VariantPolicy;
+use crate::review::{self, ReviewItem};
+use crate::schedule::Schedule;
+
+pub static MAX_RECENT_ANSWERS: usize = 100;
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Store {
+    #[serde(skip)]
+    path: PathBuf,
+    pub players: HashMap<String, Player>,
+    #[serde(default)]
+    pub stats: Stats,
+    #[serde(default)]
+    pub guilds: HashMap<String, Guild>,
+    // The shared schedule as last accepted, for checking changes to the schedule file
+    #[serde(default)]
+    pub schedule: Schedule,
+    // Answers of the shared daily puzzles by mode name and date, which later days avoid
+    #[serde(default)]
+    pub daily_answers: DailyAnswers,
+    // Changed since it was last saved, by something that can wait for the next flush
+    #[serde(skip)]
+    dirty: bool,
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct Guild {
+    #[serde(default)]
+    pub variant_policy: VariantPolicy,
+    // Daily puzzles follow the shared schedule when not set
+    #[serde(default)]
+    pub schedule: Option<Schedule>,
+    // Where the daily puzzle rolls over at midnight, UTC when not set
+    #[serde(default)]
+    pub timezone: Option<Tz>,
+    // Answers of the guild's own daily puzzles, while it has a schedule of its own
+    #[serde(default)]
+    pub daily_answers: DailyAnswers,
+}
+
+pub type DailyAnswers = BTreeMap<String, BTreeMap<NaiveDate, Ji>>;
+
+impl Guild {
+    pub fn forget_daily_answers_from(&mut self, date: NaiveDate) {
+        forget_from(&mut self.daily_answers, date);
+    }
+}
+
+fn forget_from(answers: &mut DailyAnswers, date: NaiveDate) {
+    for by_date in answers.values_mut() {
+        by_date.retain(|&d, _| d < date);
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct Player {
+    // Most recent answer is at the back
+    #[serde(default)]
+    pub recent_answers: VecDeque<Ji>,
+    #[serde(default)]
+    pub reviews: BTreeMap<Ji, ReviewItem>,
+}
+
+impl Player {
+    pub fn push_answer(&mut self, answer: Ji) {
+        self.recent_answers.retain(|&x| x != answer);
+        self.recent_answers.push_back(answer);
+        while self.recent_answers.len() > MAX_RECENT_ANSWERS {
+            self.recent_answers.pop_front();
+        }
+    }
+
+    // Missed answers enter the review queue, answers already in it are rescheduled
+    pub fn record_result(
+        &mut self,
+        answer: Ji,
+        solved: bool,
+        attempts: usize,
+        today: DateTime<Utc>,
+    ) {
+        let quality = review::quality(solved, attempts);
+        if solved {
+            if let Some(item) = self.reviews.get_mut(&answer) {
+                item.review(quality, today);
+            }
+        } else {
+            self.reviews
+                .entry(answer)
+                .or_insert_with(|| ReviewItem::new(today))
+                .review(quality, today);
+        }
+    }
+
+    // Due answers, the most overdue first
+    pub fn due_reviews(&self, today: DateTime<Utc>) -> Vec<Ji> {
+        let mut due = self
+            .reviews
+            .iter()
+            .filter(|(_, item)| item.is_due(today))
+            .collect::<Vec<_>>();
+        due.sort_by_key(|(_, item)| item.due);
+        due.into_iter().map(|(&ji, _)| ji).collect()
+    }
+}
+
+impl Store {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Store> {
+        let path = path.into();
+        let mut store = match File::open(&path) {
+            Ok(file) => serde_json::from_reader(BufReader::new(file))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                tracing::info!("No store found at {path:?}, starting with an empty one");
+                Store::default()
+            }
+            Err(e) => return Err(e.into()),
+        };
+        store.path = path;
+        Ok(store)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        // Write to a temporary file first so a crash never leaves a truncated store behind
+        let tmp = self.path.with_extension("tmp");
+        let file = File::create(&tmp)?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer(&mut writer, self)?;
+        writer.flush()?;
+        fs::rename(tmp, &self.path)?;
+        Ok(())
+    }
+
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    // Saves changes that were only marked, so frequent small ones do not each rewrite the file
+    pub fn flush(&mut self) -> Result<()> {
+        if self.dirty {
+            self.save()?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+
+    pub fn player(&self, id: &str) -> Option<&Player> {
+        self.players.get(id)
+    }
+
+    pub fn player_mut(&mut self, id: &str) -> &mut Player {
+        self.players.entry(id.to_owned()).or_default()
+    }
+
+    pub fn guild(&self, id: &str) -> Option<&Guild> {
+        self.guilds.get(id)
+    }
+
+    pub fn guild_mut(&mut self, id: &str) -> &mut Guild {
+        self.guilds.entry(id.to_owned()).or_default()
+    }
+
+    // Daily answers of a mode, those of the guild for guilds with a schedule of their own
+    pub fn daily_answers(
+        &self,
+        guild: Option<&str>,
+        mode: &str,
+    ) -> Option<&BTreeMap<NaiveDate, Ji>> {
+        match guild {
+            Some(guild) => self.guild(guild)?.daily_answers.get(mode),
+            None => self.daily_answers.get(mode),
+        }
+    }
+
+    pub fn daily_answers_mut(
+        &mut self,
+        guild: Option<&str>,
+        mode: &str,
+    ) -> &mut BTreeMap<NaiveDate, Ji> {
+        let answers = match guild {
+            Some(guild) => &mut self.guild_mut(guild).daily_answers,
+            None => &mut self.daily_answers,
+        };
+        answers.entry(mode.to_owned()).or_default()
+    }
+
+    // Days no one has played yet are made again once something they depend on changes
+    pub fn forget_daily_answers_from(&mut self, date: NaiveDate) {
+        forget_from(&mut self.daily_answers, date);
+        for guild in self.guilds.values_mut() {
+            guild.forget_daily_answers_from(date);
+        }
+        self.mark_dirty();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_answers_are_bounded_and_unique() {
+        let mut player = Player::default();
+        for c in ['一', '二', '一'] {
+            player.push_answer(Ji(c));
+        }
+        assert_eq!(player.recent_answers, [Ji('二'), Ji('一')]);
+
+        for i in 0..MAX_RECENT_ANSWERS as u32 * 2 {
+            player.push_answer(Ji(char::from_u32(0x4e00 + i).unwrap()));
+        }
+        assert_eq!(player.recent_answers.len(), MAX_RECENT_ANSWERS);
+    }
+
+    #[test]
+    fn forgets_daily_answers_from_a_date() {
+        let date = |d| NaiveDate::from_ymd_opt(2026, 11, d).unwrap();
+        let mut store = Store::default();
+        for d in 1..=3 {
+            store
+                .daily_answers_mut(None, "classic")
+                .insert(date(d), Ji('森'));
+            store
+                .daily_answers_mut(Some("guild"), "classic")
+                .insert(date(d), Ji('林'));
+        }
+        store.forget_daily_answers_from(date(3));
+        for guild in [None, Some("guild")] {
+            let answers = store.daily_answers(guild, "classic").unwrap();
+            assert_eq!(
+                answers.keys().copied().collect::<Vec<_>>(),
+                [date(1), date(2)]
+            );
+        }
+        assert!(store.daily_answers(None, "hidden").is_none());
+    }
+
+    #[test]
+    fn missed_answers_come_back() {
+        let today = DateTime::from_timestamp_millis(0).unwrap();
+        let tomorrow = today + chrono::TimeDelta::days(1);
+        let mut player = Player::default();
+        player.record_result(Ji('学'), true, 1, today);
+        player.record_result(Ji('国'), false, 5, today);
+        assert!(player.due_reviews(today).is_empty());
+        assert_eq!(player.due_reviews(tomorrow), [Ji('国')]);
+
+        player.record_result(Ji('国'), true, 1, tomorrow);
+        assert!(player.due_reviews(tomorrow).is_empty());
+    }
+}